@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::{
+    game_move::Action,
+    position::{Position, PositionError},
+    record::{Event, GameRecord},
+    solver::{Solution, Solver},
+};
+
+/// A recorded move set against the best one, as the solver sees it with
+/// every hand open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Annotation {
+    /// the index of the move in `GameRecord::events`
+    pub index: usize,
+    pub event: Event,
+    /// how many moves the seat had to choose from
+    pub choices: usize,
+    /// the best move and what it wins the seat, `None` for positions the
+    /// solver doesn't take
+    pub best: Option<Solution>,
+    /// what the seat wins after the move played, with best play from there
+    pub played: Option<i32>,
+}
+
+impl Annotation {
+    /// The points the move played gave away against the best one.
+    pub fn lost(&self) -> i32 {
+        match (self.best, self.played) {
+            (Some(best), Some(played)) => best.value - played,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisError {
+    Position(PositionError),
+    /// the event at this index isn't something the seat to move could do
+    IllegalEvent(usize, Event),
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::Position(error) => write!(f, "{}", error),
+            AnalysisError::IllegalEvent(index, event) => {
+                write!(f, "event {} ({:?}) can't happen at that point", index + 1, event)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+impl From<PositionError> for AnalysisError {
+    fn from(error: PositionError) -> Self {
+        AnalysisError::Position(error)
+    }
+}
+
+/// Goes through a recorded game and annotates every tile played. Only two
+/// player games with nothing left to draw can be solved, see
+/// `Solver::can_solve`, so other moves get no verdict.
+pub fn analyze(record: &GameRecord) -> Result<Vec<Annotation>, AnalysisError> {
+    let mut position: Position = record.start.clone();
+    position.validate()?;
+    let mut solver = Solver::new();
+    let mut annotations = Vec::new();
+
+    for (index, event) in record.events.iter().enumerate() {
+        let actions = position.legal_actions();
+        if event.seat() != position.current_player || !actions.contains(&event.action()) {
+            return Err(AnalysisError::IllegalEvent(index, *event));
+        }
+
+        if let Action::Play(_) = event.action() {
+            let best = solver.solve(&position);
+            let mut after = position.clone();
+            after.apply(event.action());
+            let played = solver.solve(&after).map(|solution| {
+                if after.current_player == position.current_player {
+                    solution.value
+                } else {
+                    -solution.value
+                }
+            });
+            annotations.push(Annotation {
+                index,
+                event: *event,
+                choices: actions.len(),
+                best,
+                played,
+            });
+        }
+        position.apply(event.action());
+    }
+    Ok(annotations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze, AnalysisError};
+    use crate::{
+        first_possible_tile_ai_player::FirstPossibleTileAIPlayer,
+        game::{Game, GameState},
+        greedy_ai_player::GreedyAIPlayer,
+        record::Event,
+    };
+
+    fn played_game(seed: u64) -> Game {
+        let mut game = Game::from_seed(seed, vec![Box::new(GreedyAIPlayer), Box::new(FirstPossibleTileAIPlayer)]);
+        loop {
+            let (next, state) = game.play();
+            game = next;
+            if let GameState::Finished { .. } = state {
+                return game;
+            }
+        }
+    }
+
+    #[test]
+    fn no_move_beats_the_best_one() {
+        for seed in 0..5 {
+            let game = played_game(seed);
+            let annotations = analyze(game.record()).unwrap();
+            let plays = game.record().events.iter().filter(|event| matches!(event, Event::Play { .. })).count();
+            assert_eq!(annotations.len(), plays);
+            for annotation in annotations {
+                assert!(annotation.best.is_some() && annotation.played.is_some());
+                assert!(annotation.lost() >= 0, "{:?}", annotation);
+                if annotation.choices == 1 {
+                    assert_eq!(annotation.lost(), 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_a_move_out_of_turn() {
+        let mut record = played_game(1).record().clone();
+        let event = record.events.remove(0);
+        record.events.insert(1, event);
+        assert!(matches!(analyze(&record), Err(AnalysisError::IllegalEvent(..))));
+    }
+}
@@ -0,0 +1,102 @@
+use std::{path::PathBuf, process, str::FromStr};
+
+use rust_domino::{game::GameMode, network::Network, training::Training};
+
+const USAGE: &str = "usage: train [--iterations N] [--games N] [--learning-rate R] [--mode block|draw]
+             [--seed N] [--from WEIGHTS] [--out DIRECTORY] [--checkpoint-every N]";
+
+struct Options {
+    iterations: u32,
+    games: u32,
+    learning_rate: f64,
+    game_mode: GameMode,
+    seed: Option<u64>,
+    from: Option<PathBuf>,
+    out: PathBuf,
+    checkpoint_every: u32,
+}
+
+fn parse<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("`{}` is not a valid value for {}", value, flag))
+}
+
+fn options() -> Result<Options, String> {
+    let mut options = Options {
+        iterations: 1000,
+        games: 64,
+        learning_rate: 1e-3,
+        game_mode: GameMode::Block,
+        seed: None,
+        from: None,
+        out: PathBuf::from("checkpoints"),
+        checkpoint_every: 50,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--iterations" => options.iterations = parse(&flag, args.next())?,
+            "--games" => options.games = parse(&flag, args.next())?,
+            "--learning-rate" => options.learning_rate = parse(&flag, args.next())?,
+            "--mode" => {
+                options.game_mode = match args.next().as_deref() {
+                    Some("block") => GameMode::Block,
+                    Some("draw") => GameMode::Draw,
+                    _ => return Err("--mode is either block or draw".to_string()),
+                }
+            }
+            "--seed" => options.seed = Some(parse(&flag, args.next())?),
+            "--from" => options.from = Some(parse(&flag, args.next())?),
+            "--out" => options.out = parse(&flag, args.next())?,
+            "--checkpoint-every" => options.checkpoint_every = parse(&flag, args.next())?,
+            _ => return Err(format!("unknown option `{}`", flag)),
+        }
+    }
+    if options.games == 0 || options.checkpoint_every == 0 {
+        return Err("--games and --checkpoint-every must be at least 1".to_string());
+    }
+    Ok(options)
+}
+
+fn main() {
+    let options = options().unwrap_or_else(|error| {
+        eprintln!("{}\n{}", error, USAGE);
+        process::exit(2);
+    });
+
+    let network = match &options.from {
+        Some(path) => Network::load(path).unwrap_or_else(|error| {
+            eprintln!("can't load {}: {}", path.display(), error);
+            process::exit(1);
+        }),
+        None => Network::new(options.seed.unwrap_or_default()),
+    };
+    let mut training = Training::new(network)
+        .games(options.games)
+        .learning_rate(options.learning_rate)
+        .game_mode(options.game_mode);
+    if let Some(seed) = options.seed {
+        training = training.seed(seed);
+    }
+
+    let checkpoint = |training: &Training| match training.checkpoint(&options.out) {
+        Ok(path) => println!("saved {}", path.display()),
+        Err(error) => {
+            eprintln!("can't save to {}: {}", options.out.display(), error);
+            process::exit(1);
+        }
+    };
+    for _ in 0..options.iterations {
+        let report = training.iterate();
+        println!(
+            "iteration {}: loss {:.4} over {} moves, {} won and {} lost against snapshots",
+            report.iteration, report.loss, report.samples, report.wins, report.losses
+        );
+        if report.iteration.is_multiple_of(options.checkpoint_every) {
+            checkpoint(&training);
+        }
+    }
+    if !options.iterations.is_multiple_of(options.checkpoint_every) {
+        checkpoint(&training);
+    }
+}
@@ -1,25 +1,32 @@
 use arrayvec::ArrayVec;
-use rand::{seq::SliceRandom, rngs::ThreadRng};
+use rand::{seq::SliceRandom, Rng};
 
-use crate::tile::Tile;
+use crate::tile::{Tile, MAX_PIP};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Boneyard {
     pub tiles: ArrayVec<Tile, 28>,
 }
 
 impl Boneyard {
     pub fn new() -> Boneyard {
+        Self::with_max_pip(MAX_PIP)
+    }
+
+    /// The double-`max_pip` set, e.g. the ten tiles up to [3|3] for 3.
+    pub fn with_max_pip(max_pip: u8) -> Boneyard {
+        assert!(max_pip <= MAX_PIP, "sets go up to double-{}", MAX_PIP);
         let mut tiles = ArrayVec::new();
-        for left in 0..7 {
-            for right in left..7 {
+        for left in 0..=max_pip {
+            for right in left..=max_pip {
                 tiles.push(Tile::new(left, right));
             }
         }
         Boneyard { tiles }
     }
 
-    pub fn shuffle(mut self, rng: &mut ThreadRng) -> Self {
+    pub fn shuffle<R: Rng + ?Sized>(mut self, rng: &mut R) -> Self {
         self.tiles.shuffle(rng);
         self
     }
@@ -35,7 +42,18 @@ impl Boneyard {
         self.tiles.pop().unwrap()
     }
 
+    /// undoes `draw`, the tile will be drawn next again
+    pub fn put_back(&mut self, tile: Tile) {
+        self.tiles.push(tile);
+    }
+
     pub fn is_empty(&self) -> bool {
         self.tiles.len() == 0
     }
 }
+
+impl Default for Boneyard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
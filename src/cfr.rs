@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use arrayvec::ArrayVec;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    game::{GameMode, GameState},
+    game_move::{Action, Move, Side, MAX_MOVES},
+    hand::Hand,
+    position::Position,
+    record::Event,
+    solver::Solver,
+};
+
+const PASS: u8 = 56;
+/// someone else drew, which tile is hidden
+const HIDDEN_DRAW: u8 = 57;
+/// the seat itself drew, followed by the tile's index
+const OWN_DRAW: u8 = 64;
+
+/// What a move looks like in an `InfoSet` history.
+pub fn play_token(mv: Move) -> u8 {
+    let side = match mv.side {
+        Side::Left => 0,
+        Side::Right => 1,
+    };
+    (mv.tile.index() * 2 + side) as u8
+}
+
+/// What `seat` saw of `event`, as one byte of an `InfoSet` history.
+pub fn observe(event: &Event, seat: usize) -> u8 {
+    match *event {
+        Event::Play { mv, .. } => play_token(mv),
+        Event::Pass { .. } => PASS,
+        Event::Draw { seat: drawer, tile } if drawer == seat => OWN_DRAW + tile.index() as u8,
+        Event::Draw { .. } => HIDDEN_DRAW,
+    }
+}
+
+/// Everything a seat can tell apart when it is to move: its hand and what
+/// it saw happen, in order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InfoSet {
+    seat: u8,
+    /// a bit per `Tile::index`
+    hand: u32,
+    history: Vec<u8>,
+}
+
+impl InfoSet {
+    /// `history` holds what `observe` makes of every event so far.
+    pub fn new(seat: usize, hand: &Hand, history: Vec<u8>) -> InfoSet {
+        InfoSet {
+            seat: seat as u8,
+            hand: hand.tiles.iter().fold(0, |bits, tile| bits | 1 << tile.index()),
+            history,
+        }
+    }
+
+    /// The info set of the player to move.
+    pub fn of(position: &Position) -> InfoSet {
+        let seat = position.current_player;
+        let history = position.history.iter().map(|step| observe(&step.event, seat)).collect();
+        InfoSet::new(seat, &position.hands[seat], history)
+    }
+}
+
+/// The legal actions in an order that doesn't depend on the order of the
+/// hand, so they line up across every deal of the same info set.
+fn actions(position: &Position) -> ArrayVec<Action, MAX_MOVES> {
+    let mut actions = position.legal_actions();
+    actions.sort_by_key(|action| match *action {
+        Action::Play(mv) => play_token(mv),
+        Action::Draw => HIDDEN_DRAW,
+        Action::Pass => PASS,
+    });
+    actions
+}
+
+struct Node {
+    actions: ArrayVec<Action, MAX_MOVES>,
+    regrets: ArrayVec<f64, MAX_MOVES>,
+    /// the strategies played, weighted by how likely the seat was to get here
+    strategy_sum: ArrayVec<f64, MAX_MOVES>,
+}
+
+impl Node {
+    fn new(actions: &[Action]) -> Node {
+        Node {
+            actions: actions.iter().copied().collect(),
+            regrets: actions.iter().map(|_| 0.0).collect(),
+            strategy_sum: actions.iter().map(|_| 0.0).collect(),
+        }
+    }
+
+    /// Regret matching: in proportion to the positive regrets, uniform
+    /// without any.
+    fn strategy(&self) -> ArrayVec<f64, MAX_MOVES> {
+        Self::normalize(self.regrets.iter().map(|regret| regret.max(0.0)).collect())
+    }
+
+    fn average(&self) -> ArrayVec<f64, MAX_MOVES> {
+        Self::normalize(self.strategy_sum.clone())
+    }
+
+    fn normalize(mut weights: ArrayVec<f64, MAX_MOVES>) -> ArrayVec<f64, MAX_MOVES> {
+        let total: f64 = weights.iter().sum();
+        let count = weights.len() as f64;
+        for weight in weights.iter_mut() {
+            *weight = if total > 0.0 { *weight / total } else { 1.0 / count };
+        }
+        weights
+    }
+}
+
+/// Counterfactual regret minimization for two player games on a reduced
+/// set, small enough to walk every line of play. Each iteration deals at
+/// random and walks the whole game from there (chance sampling). The
+/// average strategy approaches an equilibrium for the points won.
+pub struct Cfr {
+    max_pip: u8,
+    hand_size: usize,
+    game_mode: GameMode,
+    nodes: HashMap<InfoSet, Node>,
+    rng: StdRng,
+    /// iterations run so far
+    pub iterations: u64,
+}
+
+impl Cfr {
+    /// For the double-`max_pip` set with `hand_size` tiles each.
+    pub fn new(max_pip: u8, hand_size: usize) -> Cfr {
+        let tiles = (max_pip as usize + 1) * (max_pip as usize + 2) / 2;
+        assert!(2 * hand_size <= tiles, "not enough tiles to deal");
+        Cfr {
+            max_pip,
+            hand_size,
+            game_mode: GameMode::Block,
+            nodes: HashMap::new(),
+            rng: StdRng::from_entropy(),
+            iterations: 0,
+        }
+    }
+
+    pub fn game_mode(mut self, game_mode: GameMode) -> Self {
+        self.game_mode = game_mode;
+        self
+    }
+
+    /// Makes the deals repeatable.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// A fresh deal of the game being solved, for `Game::from_position`.
+    pub fn deal<R: Rng + ?Sized>(&self, rng: &mut R) -> Position {
+        let mut position = Position::deal_set(rng, 2, self.max_pip, self.hand_size);
+        position.game_mode = self.game_mode;
+        position
+    }
+
+    pub fn train(&mut self, iterations: u32) {
+        for _ in 0..iterations {
+            let mut position = Position::deal_set(&mut self.rng, 2, self.max_pip, self.hand_size);
+            position.game_mode = self.game_mode;
+            self.walk(&mut position, [1.0, 1.0]);
+            self.iterations += 1;
+        }
+    }
+
+    /// How many info sets were reached so far.
+    pub fn info_sets(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The average strategy so far.
+    pub fn strategy(&self) -> Strategy {
+        let table = self
+            .nodes
+            .iter()
+            .map(|(info_set, node)| (info_set.clone(), node.actions.iter().copied().zip(node.average()).collect()))
+            .collect();
+        Strategy { table }
+    }
+
+    /// Updates the regrets of every info set below `position` and returns
+    /// its value to seat 0. `reach` is how likely each seat's own choices
+    /// were to lead here.
+    fn walk(&mut self, position: &mut Position, reach: [f64; 2]) -> f64 {
+        if let state @ GameState::Finished { .. } = position.state() {
+            return Solver::value(&state) as f64;
+        }
+
+        let actions = actions(position);
+        if actions.len() == 1 {
+            position.apply(actions[0]);
+            let value = self.walk(position, reach);
+            position.undo();
+            return value;
+        }
+
+        let seat = position.current_player;
+        let info_set = InfoSet::of(position);
+        let strategy = self
+            .nodes
+            .entry(info_set.clone())
+            .or_insert_with(|| Node::new(&actions))
+            .strategy();
+
+        let mut values = ArrayVec::<f64, MAX_MOVES>::new();
+        for (action, probability) in actions.iter().zip(strategy.iter()) {
+            let mut reach = reach;
+            reach[seat] *= probability;
+            position.apply(*action);
+            values.push(self.walk(position, reach));
+            position.undo();
+        }
+        let value: f64 = values.iter().zip(strategy.iter()).map(|(value, probability)| value * probability).sum();
+
+        // seat 1 is after the lowest value for seat 0
+        let sign = if seat == 0 { 1.0 } else { -1.0 };
+        let node = self.nodes.get_mut(&info_set).unwrap();
+        for index in 0..actions.len() {
+            node.regrets[index] += reach[1 - seat] * sign * (values[index] - value);
+            node.strategy_sum[index] += reach[seat] * strategy[index];
+        }
+        value
+    }
+}
+
+/// What `Cfr` learned: how likely each action is in every info set it
+/// reached.
+pub struct Strategy {
+    table: HashMap<InfoSet, ArrayVec<(Action, f64), MAX_MOVES>>,
+}
+
+impl Strategy {
+    /// `None` for an info set never reached while training.
+    pub fn get(&self, info_set: &InfoSet) -> Option<&[(Action, f64)]> {
+        self.table.get(info_set).map(|actions| actions.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cfr, InfoSet};
+    use crate::{
+        game::GameMode,
+        game_move::Action,
+        position::fixtures::{go_out_first, going_out_first},
+    };
+
+    #[test]
+    fn learns_the_only_way_to_go_out_first() {
+        let position = go_out_first();
+
+        // always the same deal, so nothing is really hidden
+        let mut cfr = Cfr::new(6, 7);
+        for _ in 0..200 {
+            cfr.walk(&mut position.clone(), [1.0, 1.0]);
+        }
+
+        let strategy = cfr.strategy();
+        let root = strategy.get(&InfoSet::of(&position)).unwrap();
+        let best = Action::Play(going_out_first());
+        let (_, probability) = root.iter().find(|(action, _)| *action == best).unwrap();
+        assert!(*probability > 0.9, "{:?}", root);
+    }
+
+    #[test]
+    fn every_reached_info_set_has_a_distribution() {
+        let mut cfr = Cfr::new(2, 2).game_mode(GameMode::Draw).seed(1);
+        cfr.train(100);
+        assert!(cfr.info_sets() > 0);
+
+        let strategy = cfr.strategy();
+        for actions in strategy.table.values() {
+            let total: f64 = actions.iter().map(|(_, probability)| probability).sum();
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+    }
+}
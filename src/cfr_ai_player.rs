@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    cfr::{observe, play_token, InfoSet, Strategy},
+    choose_tile_strategy::ChooseTileStrategy,
+    game::GameMode,
+    game_move::{legal_moves, Action, Move, Side},
+    game_observer::GameObserver,
+    hand::Hand,
+    player::Player,
+    record::Event,
+    snake::Snake,
+    tile::Tile,
+};
+
+/// Stands in for an opponent's move until the snake shows which end it
+/// went on.
+const UNRESOLVED: u8 = u8::MAX;
+
+/// Plays a `Strategy` found by `Cfr`, sampling its moves by their
+/// probabilities. It follows the game from the callbacks alone, so it has
+/// to be one of two players with seat 0 moving first, as `Cfr::deal`
+/// deals. Info sets the strategy never reached get the heaviest tile.
+pub struct CfrAIPlayer {
+    strategy: Arc<Strategy>,
+    rng: StdRng,
+    seat: usize,
+    /// what `observe` made of every event so far
+    history: Vec<u8>,
+    /// the snake as far as the history is resolved
+    snake: Snake,
+    /// the tiles standing behind `UNRESOLVED`, in the order played
+    unresolved: Vec<Tile>,
+    /// whether the turn came to me and I haven't played since
+    my_turn: bool,
+}
+
+impl CfrAIPlayer {
+    pub fn new(strategy: Arc<Strategy>) -> Self {
+        CfrAIPlayer {
+            strategy,
+            rng: StdRng::from_entropy(),
+            seat: 0,
+            history: Vec::new(),
+            snake: Snake::new(),
+            unresolved: Vec::new(),
+            my_turn: false,
+        }
+    }
+
+    /// Makes the sampled moves repeatable.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// The game never tells a player it passed, but an opponent acting
+    /// while the turn was mine means I did.
+    fn opponent_acted(&mut self) {
+        if self.my_turn {
+            self.history.push(observe(&Event::Pass { seat: self.seat }, self.seat));
+            self.my_turn = false;
+        }
+    }
+
+    /// Works out which end the opponent's tiles went on from where they
+    /// lie in the snake now.
+    fn resolve(&mut self, snake: &Snake) {
+        let position = |tile: Tile| snake.tiles.iter().position(|on| on.normalized() == tile.normalized());
+        for tile in self.unresolved.drain(..) {
+            let side = match self.snake.tiles.first() {
+                Some(first) if position(tile) < position(*first) => Side::Left,
+                // on an empty snake either side lays the same line
+                _ => Side::Right,
+            };
+            self.snake.play(tile, side).unwrap();
+            let index = self.history.iter().position(|token| *token == UNRESOLVED).unwrap();
+            self.history[index] = play_token(Move::new(tile, side));
+        }
+    }
+
+    fn info_set(&mut self, hand: &Hand, snake: &Snake) -> InfoSet {
+        self.resolve(snake);
+        InfoSet::new(self.seat, hand, self.history.clone())
+    }
+}
+
+impl Player for CfrAIPlayer {}
+
+impl ChooseTileStrategy for CfrAIPlayer {
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        let info_set = self.info_set(hand, snake);
+        let mv = match self.strategy.get(&info_set) {
+            Some(actions) => {
+                let mut left = self.rng.gen::<f64>();
+                let mut chosen = actions[actions.len() - 1].0;
+                for (action, probability) in actions {
+                    left -= probability;
+                    if left < 0.0 {
+                        chosen = *action;
+                        break;
+                    }
+                }
+                match chosen {
+                    Action::Play(mv) => mv,
+                    _ => unreachable!("the player only chooses when a tile fits"),
+                }
+            }
+            None => legal_moves(hand, snake).into_iter().max_by_key(|mv| mv.tile.score()).unwrap(),
+        };
+
+        self.history.push(play_token(mv));
+        self.snake.play(mv.tile, mv.side).unwrap();
+        self.my_turn = false;
+        mv
+    }
+}
+
+impl GameObserver for CfrAIPlayer {
+    fn game_started(
+        &mut self,
+        seat: usize,
+        _hand: &Hand,
+        _hand_sizes: &[usize],
+        snake: &Snake,
+        _game_mode: GameMode,
+        _partner: Option<usize>,
+    ) {
+        self.seat = seat;
+        self.history.clear();
+        self.snake = snake.clone();
+        self.unresolved.clear();
+        self.my_turn = seat == 0;
+    }
+
+    fn opponent_drew_tile(&mut self, seat: usize) {
+        self.opponent_acted();
+        self.history.push(observe(&Event::Draw { seat, tile: Tile::new(0, 0) }, self.seat));
+    }
+
+    fn i_drew_tile(&mut self, tile: Tile) {
+        self.history.push(observe(&Event::Draw { seat: self.seat, tile }, self.seat));
+    }
+
+    fn opponent_played_tile(&mut self, _seat: usize, tile: Tile) {
+        self.opponent_acted();
+        self.history.push(UNRESOLVED);
+        self.unresolved.push(tile);
+        self.my_turn = true;
+    }
+
+    fn opponent_was_blocked(&mut self, seat: usize, _pips: [u8; 2]) {
+        self.opponent_acted();
+        self.history.push(observe(&Event::Pass { seat }, self.seat));
+        self.my_turn = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::CfrAIPlayer;
+    use crate::{
+        cfr::{Cfr, InfoSet},
+        choose_tile_strategy::ChooseTileStrategy,
+        first_possible_tile_ai_player::FirstPossibleTileAIPlayer,
+        game::{GameMode, GameState},
+        game_move::Action,
+        game_observer::GameObserver,
+        record::Event,
+        simulation::{PlayerFactory, Simulation},
+    };
+
+    #[test]
+    fn follows_the_same_info_sets_as_training() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for (seat, game_mode) in [0, 1].into_iter().flat_map(|seat| [(seat, GameMode::Draw), (seat, GameMode::Block)]) {
+            let cfr = Cfr::new(3, 3).game_mode(game_mode);
+            for _ in 0..20 {
+                let mut position = cfr.deal(&mut rng);
+                let mut player = CfrAIPlayer::new(Arc::new(cfr.strategy()));
+                let sizes: Vec<usize> = position.hands.iter().map(|hand| hand.tiles.len()).collect();
+                player.game_started(seat, &position.hands[seat], &sizes, &position.snake, game_mode, None);
+
+                while position.state() == GameState::Playing {
+                    let actions = position.legal_actions();
+                    let action = actions[rng.gen_range(0..actions.len())];
+                    if position.current_player == seat && matches!(action, Action::Play(_)) {
+                        let expected = InfoSet::of(&position);
+                        let hand = position.hands[seat].clone();
+                        assert_eq!(player.info_set(&hand, &position.snake), expected);
+                        // make the player's own move the one taken
+                        let mv = player.choose_move(&hand, &position.snake);
+                        position.apply(Action::Play(mv));
+                        continue;
+                    }
+                    // what `Game` tells the other seats
+                    match position.apply(action) {
+                        Event::Draw { seat: drawer, tile } if drawer == seat => player.i_drew_tile(tile),
+                        Event::Draw { seat: drawer, .. } if drawer != seat => player.opponent_drew_tile(drawer),
+                        Event::Play { seat: player_seat, mv } if player_seat != seat => {
+                            player.opponent_played_tile(player_seat, mv.tile)
+                        }
+                        Event::Pass { seat: passer } if passer != seat => {
+                            let ends = [position.snake.left().unwrap(), position.snake.right().unwrap()];
+                            player.opponent_was_blocked(passer, ends)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn beats_the_first_possible_tile() {
+        let mut cfr = Cfr::new(3, 3).seed(1);
+        cfr.train(3000);
+        let strategy = Arc::new(cfr.strategy());
+
+        let cfr: PlayerFactory = Arc::new(move |seed| Box::new(CfrAIPlayer::new(strategy.clone()).seed(seed)));
+        let first_possible: PlayerFactory = Arc::new(|_| Box::new(FirstPossibleTileAIPlayer));
+        let report = Simulation::new(cfr, first_possible).set(3, 3).games(4000).threads(2).seed(2).run();
+        assert!(report.score_interval().low > 0.5, "{}", report);
+    }
+}
@@ -1,14 +1,22 @@
-use crate::{hand::Hand, snake::Snake, tile::Tile};
+use crate::{game_move::Move, hand::Hand, snake::Snake};
 
-enum PossibleTile {
-    Yes, No, Maybe
-}
-
-struct PossibleHand {
-    tiles: [PossibleTile; 28],
-    tiles_count: u8,
+/// A seat's answer on its turn.
+pub enum Choice {
+	Play(Move),
+	/// undo everything back to my previous turn, see `Game::take_back`
+	TakeBack,
+	/// leave the game where it is, see `Game::abandoned`
+	Quit,
 }
 
 pub trait ChooseTileStrategy {
-	fn choose_tile(&mut self, snake: &Snake) -> Tile;
+	/// Only called when a tile in `hand` is playable. The game takes the
+	/// tile out of the hand once the move is made.
+	fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move;
+
+	/// What the game asks for; only seats that may take back their moves
+	/// need more than `choose_move`.
+	fn choose(&mut self, hand: &Hand, snake: &Snake) -> Choice {
+		Choice::Play(self.choose_move(hand, snake))
+	}
 }
@@ -0,0 +1,355 @@
+use arrayvec::ArrayVec;
+
+use crate::{
+    choose_tile_strategy::ChooseTileStrategy,
+    game::{GameMode, MAX_PLAYERS},
+    game_move::{legal_moves, Move, Side, MAX_MOVES},
+    game_observer::GameObserver,
+    hand::{Hand, HandTrait},
+    knowledge::Knowledge,
+    player::Player,
+    possible_hand::PossibleHand,
+    snake::Snake,
+    tile::Tile,
+};
+
+/// What going out is worth on top of the pips won.
+const WIN: f64 = 100.0;
+/// What every tile still in a hand counts on top of its pips.
+const TILE: f64 = 3.0;
+
+/// Looks a few turns ahead, weighing every reply by how likely the other
+/// seats are to hold the tiles for it, going by their `PossibleHand`s.
+/// Opponents pick the side that hurts most, partners the one that helps.
+pub struct ExpectimaxAIPlayer {
+    depth: u32,
+    knowledge: Knowledge,
+}
+
+impl Default for ExpectimaxAIPlayer {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+/// The game as one seat sees it.
+#[derive(Clone)]
+struct Belief {
+    current_player: usize,
+    hand: Hand,
+    snake: Snake,
+    /// indexed by seat, mine is unused
+    others: ArrayVec<PossibleHand, MAX_PLAYERS>,
+    boneyard: usize,
+    /// how many seats in a row passed
+    passes: usize,
+}
+
+impl Belief {
+    /// The unseen tiles `seat` could hold.
+    fn candidates(&self, seat: usize) -> ArrayVec<Tile, 28> {
+        self.others[seat].possible_tiles.clone()
+    }
+
+    fn next_player(&mut self) {
+        self.current_player = (self.current_player + 1) % self.others.len();
+    }
+
+    fn played(&mut self, tile: Tile, side: Side) {
+        self.snake.play(tile, side).unwrap();
+        for possible in self.others.iter_mut() {
+            possible.remove_tile(tile);
+        }
+        self.passes = 0;
+        self.next_player();
+    }
+}
+
+impl ExpectimaxAIPlayer {
+    /// `depth` is counted in turns, draws included.
+    pub fn new(depth: u32) -> Self {
+        ExpectimaxAIPlayer {
+            depth,
+            knowledge: Knowledge::default(),
+        }
+    }
+
+    /// What I know at my turn.
+    fn belief(&self, hand: &Hand, snake: &Snake) -> Belief {
+        let unseen = Knowledge::unseen(hand, snake).len();
+        let held: usize = (0..self.knowledge.hands.len())
+            .filter(|seat| *seat != self.knowledge.seat)
+            .map(|seat| self.knowledge.hands[seat].size as usize)
+            .sum();
+        Belief {
+            current_player: self.knowledge.seat,
+            hand: hand.clone(),
+            snake: snake.clone(),
+            others: self.knowledge.hands.clone(),
+            boneyard: unseen.saturating_sub(held),
+            passes: 0,
+        }
+    }
+
+    fn on_my_team(&self, seat: usize) -> bool {
+        seat == self.knowledge.seat || Some(seat) == self.knowledge.partner
+    }
+
+    /// Pips for my hand, the expected pips for everyone else's.
+    fn pips(&self, belief: &Belief, seat: usize) -> f64 {
+        if seat == self.knowledge.seat {
+            return belief.hand.score() as f64;
+        }
+        let candidates = belief.candidates(seat);
+        if candidates.is_empty() {
+            return 0.0;
+        }
+        let average = candidates.iter().map(|tile| tile.score() as f64).sum::<f64>() / candidates.len() as f64;
+        average * belief.others[seat].size as f64
+    }
+
+    /// The chance `seat` holds `tile`. Every other seat that could hold it
+    /// and the boneyard get a share by the part of their candidates they
+    /// hold, so a tile only `seat` could have is likelier theirs.
+    fn holding(&self, belief: &Belief, seat: usize, tile: Tile) -> f64 {
+        let share = |possible: &PossibleHand| {
+            if possible.contains(tile) {
+                (possible.size as f64 / possible.possible_tiles.len() as f64).min(1.0)
+            } else {
+                0.0
+            }
+        };
+        let others = (0..belief.others.len()).filter(|other| *other != self.knowledge.seat);
+        let unseen = belief.boneyard + others.clone().map(|other| self.size(belief, other)).sum::<usize>();
+        let total = belief.boneyard as f64 / unseen.max(1) as f64
+            + others.map(|other| share(&belief.others[other])).sum::<f64>();
+        if total > 0.0 {
+            share(&belief.others[seat]) / total
+        } else {
+            0.0
+        }
+    }
+
+    fn size(&self, belief: &Belief, seat: usize) -> usize {
+        if seat == self.knowledge.seat {
+            belief.hand.tiles.len()
+        } else {
+            belief.others[seat].size as usize
+        }
+    }
+
+    /// Better for my team the higher it is.
+    fn evaluate(&self, belief: &Belief) -> f64 {
+        (0..belief.others.len())
+            .map(|seat| {
+                let left = TILE * self.size(belief, seat) as f64 + self.pips(belief, seat);
+                if self.on_my_team(seat) {
+                    -left
+                } else {
+                    left
+                }
+            })
+            .sum()
+    }
+
+    fn value(&self, belief: &Belief, depth: u32) -> f64 {
+        let players = belief.others.len();
+        if let Some(out) = (0..players).find(|seat| self.size(belief, *seat) == 0) {
+            let sign = if self.on_my_team(out) { 1.0 } else { -1.0 };
+            return sign * WIN + self.evaluate(belief);
+        }
+        if depth == 0 || belief.passes == players {
+            return self.evaluate(belief);
+        }
+
+        if belief.current_player == self.knowledge.seat {
+            self.my_turn(belief, depth)
+        } else {
+            self.their_turn(belief, depth)
+        }
+    }
+
+    fn my_turn(&self, belief: &Belief, depth: u32) -> f64 {
+        let moves = legal_moves(&belief.hand, &belief.snake);
+        if !moves.is_empty() {
+            return moves
+                .iter()
+                .map(|mv| self.after_my_move(belief, *mv, depth))
+                .fold(f64::MIN, f64::max);
+        }
+
+        if self.knowledge.game_mode == GameMode::Draw && belief.boneyard > 0 {
+            // any tile nobody has been seen with could come up
+            let unseen = Knowledge::unseen(&belief.hand, &belief.snake);
+            let chance = 1.0 / unseen.len() as f64;
+            return unseen
+                .iter()
+                .map(|tile| {
+                    let mut next = belief.clone();
+                    next.hand.add(*tile);
+                    next.boneyard -= 1;
+                    for possible in next.others.iter_mut() {
+                        possible.remove_tile(*tile);
+                    }
+                    chance * self.value(&next, depth - 1)
+                })
+                .sum();
+        }
+        let mut next = belief.clone();
+        next.passes += 1;
+        next.next_player();
+        self.value(&next, depth - 1)
+    }
+
+    fn after_my_move(&self, belief: &Belief, mv: Move, depth: u32) -> f64 {
+        let mut next = belief.clone();
+        next.hand.remove(mv.tile);
+        next.played(mv.tile, mv.side);
+        self.value(&next, depth - 1)
+    }
+
+    fn their_turn(&self, belief: &Belief, depth: u32) -> f64 {
+        let seat = belief.current_player;
+        let playable: ArrayVec<Tile, 28> = belief
+            .candidates(seat)
+            .into_iter()
+            .filter(|tile| belief.snake.is_playable(*tile))
+            .collect();
+        let blocked = belief.others[seat].chance_without(|tile| belief.snake.is_playable(*tile));
+
+        let mut value = 0.0;
+        if blocked > 0.0 {
+            let mut next = belief.clone();
+            if let (Some(left), Some(right)) = (belief.snake.left(), belief.snake.right()) {
+                next.others[seat].remove_tiles_with_pips([left, right]);
+            }
+            if self.knowledge.game_mode == GameMode::Draw && belief.boneyard > 0 {
+                next.others[seat].size += 1;
+                next.boneyard -= 1;
+                // whatever they drew could be anything still unseen
+                next.others[seat].possible_tiles = Knowledge::unseen(&belief.hand, &belief.snake);
+            } else {
+                next.passes += 1;
+                next.next_player();
+            }
+            value += blocked * self.value(&next, depth - 1);
+        }
+
+        // each reply by how likely they are to hold its tile
+        let weights: ArrayVec<f64, 28> = playable.iter().map(|tile| self.holding(belief, seat, *tile)).collect();
+        let total: f64 = weights.iter().sum();
+        if total > 0.0 {
+            let helps_me = self.on_my_team(seat);
+            for (tile, weight) in playable.into_iter().zip(weights) {
+                let sides = [Side::Left, Side::Right]
+                    .into_iter()
+                    .filter(|side| belief.snake.fits(tile, *side))
+                    .map(|side| {
+                        let mut next = belief.clone();
+                        next.others[seat].size -= 1;
+                        next.played(tile, side);
+                        self.value(&next, depth - 1)
+                    });
+                let best = if helps_me {
+                    sides.fold(f64::MIN, f64::max)
+                } else {
+                    sides.fold(f64::MAX, f64::min)
+                };
+                value += (1.0 - blocked) * weight / total * best;
+            }
+        }
+        value
+    }
+}
+
+impl Player for ExpectimaxAIPlayer {}
+
+impl ChooseTileStrategy for ExpectimaxAIPlayer {
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        let moves = legal_moves(hand, snake);
+        if moves.len() == 1 || self.depth == 0 {
+            return moves[0];
+        }
+
+        let belief = self.belief(hand, snake);
+        let values: ArrayVec<f64, MAX_MOVES> = moves
+            .iter()
+            .map(|mv| self.after_my_move(&belief, *mv, self.depth))
+            .collect();
+        let best = (0..moves.len())
+            .max_by(|a, b| values[*a].total_cmp(&values[*b]))
+            .unwrap();
+        moves[best]
+    }
+}
+
+impl GameObserver for ExpectimaxAIPlayer {
+    fn game_started(
+        &mut self,
+        seat: usize,
+        hand: &Hand,
+        hand_sizes: &[usize],
+        snake: &Snake,
+        game_mode: GameMode,
+        partner: Option<usize>,
+    ) {
+        self.knowledge.game_started(seat, hand, hand_sizes, snake, game_mode, partner);
+    }
+
+    fn opponent_drew_tile(&mut self, seat: usize) {
+        self.knowledge.opponent_drew_tile(seat);
+    }
+
+    fn i_drew_tile(&mut self, tile: Tile) {
+        self.knowledge.i_drew_tile(tile);
+    }
+
+    fn opponent_played_tile(&mut self, seat: usize, tile: Tile) {
+        self.knowledge.opponent_played_tile(seat, tile);
+    }
+
+    fn opponent_was_blocked(&mut self, seat: usize, pips: [u8; 2]) {
+        self.knowledge.opponent_was_blocked(seat, pips);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpectimaxAIPlayer;
+    use crate::{
+        choose_tile_strategy::ChooseTileStrategy,
+        game::GameMode,
+        game_move::{Move, Side},
+        game_observer::GameObserver,
+        position::fixtures::{hand, position},
+        tile::Tile,
+    };
+
+    #[test]
+    fn a_tile_fewer_seats_could_hold_is_likelier_theirs() {
+        let (hand, snake) = (hand(&[(0, 0), (6, 6)]), position(&[], &[(4, 4)]).snake);
+        let mut player = ExpectimaxAIPlayer::new(3);
+        player.game_started(0, &hand, &[2, 5, 5], &snake, GameMode::Block, None);
+        // seat 2 has no 5, so seat 1 shares every 5 with the boneyard alone
+        player.opponent_was_blocked(2, [5, 5]);
+
+        let belief = player.belief(&hand, &snake);
+        let five = player.holding(&belief, 1, Tile::new(5, 3));
+        let three = player.holding(&belief, 1, Tile::new(3, 3));
+        assert!(five > three, "{} {}", five, three);
+        assert_eq!(player.holding(&belief, 2, Tile::new(5, 3)), 0.0);
+    }
+
+    #[test]
+    fn lays_a_light_double_to_keep_the_opponent_blocked() {
+        let (hand, snake) = (hand(&[(6, 5), (0, 0)]), position(&[], &[(0, 6)]).snake);
+        let mut player = ExpectimaxAIPlayer::new(3);
+        player.game_started(0, &hand, &[2, 1], &snake, GameMode::Block, None);
+        // seat 1 holds one last tile, neither a 0 nor a 6
+        player.opponent_was_blocked(1, [0, 6]);
+
+        // [6|5] would leave seat 1 a 5 to go out on, after [0|0] they pass
+        // and [6|5] goes out
+        assert_eq!(player.choose_move(&hand, &snake), Move::new(Tile::new(0, 0), Side::Left));
+    }
+}
@@ -1,25 +1,13 @@
-use crate::{hand::{Hand, HasHandTrait}, snake::Snake, tile::Tile, player::Player, choose_tile_strategy::ChooseTileStrategy, game_observer::GameObserver};
+use crate::{hand::Hand, snake::Snake, player::Player, choose_tile_strategy::ChooseTileStrategy, game_move::{legal_moves, Move}, game_observer::GameObserver};
 
 #[derive(Default)]
-pub struct FirstPossibleTileAIPlayer {
-	pub hand: Hand,
-}
+pub struct FirstPossibleTileAIPlayer;
 
 impl Player for FirstPossibleTileAIPlayer {}
 
-impl HasHandTrait for FirstPossibleTileAIPlayer {
-    fn hand(&self) -> &Hand {
-        &self.hand
-    }
-    fn hand_mut(&mut self) -> &mut Hand {
-        &mut self.hand
-    }
-}
-
 impl ChooseTileStrategy for  FirstPossibleTileAIPlayer {
-    fn choose_tile(&mut self, snake: &Snake) -> Tile {
-        let tile_index = self.hand.tiles.iter().position(|tile| snake.is_playable(*tile)).unwrap();
-        self.hand.tiles.remove(tile_index)
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        legal_moves(hand, snake)[0]
     }
 }
 
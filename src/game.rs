@@ -1,255 +1,384 @@
-use std::{
-    env::current_exe,
-    fmt::{write, Debug},
-    thread::current,
-};
+use std::fmt::Debug;
 
-use rand::rngs::ThreadRng;
+use arrayvec::ArrayVec;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use crate::{
     boneyard::Boneyard,
-    hand::{Hand, HandTrait},
+    choose_tile_strategy::Choice,
+    game_move::Action,
+    hand::Hand,
     player::Player,
+    position::{Position, PositionError},
+    record::{Event, GameRecord},
     snake::Snake,
-    tile::Tile,
 };
 
-pub struct Game<P1: Player, P2: Player> {
-    blocked_counter: u8,
-    current_player: u8,
-    players: (P1, P2),
-    snake: Snake,
-    boneyard: Boneyard,
-    game_mode: GameMode,
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 4;
+
+/// A `Position` played out by the players in its seats. The rules live in
+/// `Position::apply`, the game asks each seat for its move in turn and tells
+/// everyone else what happened.
+pub struct Game {
+    started: bool,
+    players: ArrayVec<Box<dyn Player>, MAX_PLAYERS>,
+    position: Position,
+    record: GameRecord,
+    /// taken back with `undo`, latest last
+    undone: Vec<Event>,
+    abandoned: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Winner {
-    Player0,
-    Player1,
+    Seat(usize),
+    /// see `Game::team`
+    Team(usize),
+}
+
+impl Winner {
+    pub fn contains(&self, seat: usize) -> bool {
+        match *self {
+            Winner::Seat(winner) => winner == seat,
+            Winner::Team(team) => Game::team(seat) == team,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameMode {
     Draw,
     Block,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameState {
     Playing,
     Finished {
         winner: Option<Winner>,
-        score_0: i32,
-        score_1: i32,
+        /// indexed by seat, partners share their team's score
+        scores: ArrayVec<i32, MAX_PLAYERS>,
     },
 }
 
-impl<P1: Player, P2: Player> Game<P1, P2> {
-    pub fn new(threadRng: &mut ThreadRng) -> Game<P1, P2> {
-        let mut boneyard = Boneyard::new().shuffle(threadRng);
+/// Everything about a game but the players themselves, see `Game::snapshot`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameSnapshot {
+    pub position: Position,
+    pub record: GameRecord,
+}
+
+impl Game {
+    /// Seats the players in the given order and deals them their hands.
+    pub fn new<R: Rng + ?Sized>(rng: &mut R, players: Vec<Box<dyn Player>>) -> Game {
+        assert!(
+            (MIN_PLAYERS..=MAX_PLAYERS).contains(&players.len()),
+            "a game needs between {} and {} players",
+            MIN_PLAYERS,
+            MAX_PLAYERS
+        );
+
+        let position = Position::deal(rng, players.len());
+        Self::from_position(position, players).expect("a fresh deal is always valid")
+    }
+
+    /// Seats the players in the given order, each playing the position's hand
+    /// for their seat.
+    pub fn from_position(position: Position, players: Vec<Box<dyn Player>>) -> Result<Game, PositionError> {
+        position.validate()?;
+        if position.hands.len() != players.len() {
+            return Err(PositionError::SeatCount {
+                hands: position.hands.len(),
+                players: players.len(),
+            });
+        }
+
+        Ok(Game {
+            started: false,
+            players: players.into_iter().collect(),
+            record: GameRecord::new(position.clone()),
+            position,
+            undone: Vec::new(),
+            abandoned: false,
+        })
+    }
+
+    /// Picks a game back up from a snapshot, with new players in the seats.
+    pub fn from_snapshot(snapshot: GameSnapshot, players: Vec<Box<dyn Player>>) -> Result<Game, PositionError> {
+        let mut game = Self::from_position(snapshot.position, players)?;
+        game.record = snapshot.record;
+        Ok(game)
+    }
 
-        let mut player_0: P1 = Default::default();
-        let mut player_1: P2 = Default::default();
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            position: self.position.clone(),
+            record: self.record.clone(),
+        }
+    }
 
-        player_0.hand_mut().add_multiple(boneyard.draw_n(7));
-        player_1.hand_mut().add_multiple(boneyard.draw_n(7));
+    /// The same seed and player count always deal the same hands and boneyard.
+    pub fn from_seed(seed: u64, players: Vec<Box<dyn Player>>) -> Game {
+        let mut game = Self::new(&mut StdRng::seed_from_u64(seed), players);
+        game.record.seed = Some(seed);
+        game
+    }
 
-        player_0.game_started();
-        player_1.game_started();
-        
-        Game {
-            blocked_counter: 0,
-            current_player: 0,
-            players: (player_0, player_1),
-            snake: Snake::new(),
-            boneyard,
-            game_mode: GameMode::Block,
+    /// Two players take seven tiles each, three or four players take five,
+    /// so even four players leave eight tiles in the boneyard to draw from.
+    pub fn hand_size(player_count: usize) -> usize {
+        match player_count {
+            2 => 7,
+            _ => 5,
         }
     }
 
+    /// In `GameMode::Draw` a player who cannot play draws from the boneyard
+    /// until they can, and only passes once the boneyard is empty.
+    pub fn game_mode(mut self, game_mode: GameMode) -> Self {
+        self.position.game_mode = game_mode;
+        self.record.start.game_mode = game_mode;
+        self
+    }
+
+    /// Seats 0 and 2 play against seats 1 and 3. A team wins together when
+    /// either partner goes out, and a blocked game goes to the team with the
+    /// fewest pips.
+    pub fn partnership(mut self, partnership: bool) -> Self {
+        assert!(
+            !partnership || self.players.len() == 4,
+            "partnership play needs four players"
+        );
+        self.position.partnership = partnership;
+        self.record.start.partnership = partnership;
+        self
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.position.current_player
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn hand(&self, seat: usize) -> &Hand {
+        &self.position.hands[seat]
+    }
+
+    pub fn snake(&self) -> &Snake {
+        &self.position.snake
+    }
+
+    pub fn boneyard(&self) -> &Boneyard {
+        &self.position.boneyard
+    }
+
+    /// The game so far, see `GameRecord` for saving and loading it.
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    pub(crate) fn record_mut(&mut self) -> &mut GameRecord {
+        &mut self.record
+    }
+
+    /// Whether a seat answered `Choice::Quit`. The game stays where it was
+    /// left, `play` doesn't go on with it.
+    pub fn abandoned(&self) -> bool {
+        self.abandoned
+    }
+
+    pub fn team(seat: usize) -> usize {
+        seat % 2
+    }
+
+    /// Plays one turn: the current player draws until a tile fits, and then
+    /// either plays, passes or takes back their previous turn.
     pub fn play(mut self) -> (Self, GameState) {
-        if self.blocked_counter == 2 {
-            return self.game_finished();
+        // the players only hear about the game once every option is set, and
+        // again after anything was taken back
+        if !self.started {
+            self.started = true;
+            self.introduce();
+        }
+        if self.abandoned {
+            return (self, GameState::Playing);
         }
 
-        if self.current_player == 0 {
-            let current_player = &mut self.players.0;
-            let opponent = &mut self.players.1;
+        if let state @ GameState::Finished { .. } = self.position.state() {
+            self.finish(&state);
+            return (self, state);
+        }
 
-            let is_boneyard_empty = self.game_mode == GameMode::Block || self.boneyard.is_empty();
-            let is_hand_playable = Self::is_hand_playable(&current_player.hand(), &self.snake);
-    
-            match (!is_hand_playable, is_boneyard_empty) {
-                (true, true) => {
-                    self.blocked_counter += 1;
+        self.undone.clear();
+        let seat = self.position.current_player;
 
-                    // player cannot be blocked if there aren't any tiles in the snake
-                    assert!(!self.snake.is_empty());
-                    opponent.opponent_was_blocked([self.snake.left().unwrap(), self.snake.right().unwrap()]);
+        // draw until a tile fits, or pass when that is not allowed
+        loop {
+            match self.position.legal_actions()[0] {
+                Action::Play(_) => break,
+                Action::Draw => self.apply(Action::Draw),
+                Action::Pass => {
+                    self.apply(Action::Pass);
                     return (self, GameState::Playing);
                 }
-                (true, false) => {
-                    while !self.boneyard.is_empty() {
-                        let tile = self.boneyard.draw();
-                        current_player.hand_mut().add(tile);
-                        opponent.opponent_drew_tile();
-                        current_player.i_drew_tile(tile);
-                        if self.snake.is_playable(tile) {
-                            break;
-                        }
-                    }
-    
-                    if !Self::is_hand_playable(&current_player.hand(), &self.snake) {
-                        self.blocked_counter += 1;
-                        // player cannot be blocked if there aren't any tiles in the snake
-                        assert!(!self.snake.is_empty());
-                        opponent.opponent_was_blocked([self.snake.left().unwrap(), self.snake.right().unwrap()]);
-                        return (self, GameState::Playing);
-                    }
-                }
-                (false, _) => {}
-            };
-    
-            assert!(Self::is_hand_playable(&current_player.hand(), &self.snake));
-    
-            let tile = current_player.choose_tile(&self.snake);
-            self.snake.add(tile);
-
-            opponent.opponent_played_tile(tile);
-    
-            if current_player.hand().is_empty() {
-                return self.game_finished();
             }
-    
-            self.current_player = (self.current_player + 1) % 2;
-            (self, GameState::Playing)
-
-        } else {
-            let current_player = &mut self.players.1;
-            let opponent = &mut self.players.0;
-
-            let is_boneyard_empty = self.game_mode == GameMode::Block || self.boneyard.is_empty();
-            let is_hand_playable = Self::is_hand_playable(&current_player.hand(), &self.snake);
-    
-            match (!is_hand_playable, is_boneyard_empty) {
-                (true, true) => {
-                    self.blocked_counter += 1;
-
-                    // player cannot be blocked if there aren't any tiles in the snake
-                    assert!(!self.snake.is_empty());
-                    opponent.opponent_was_blocked([self.snake.left().unwrap(), self.snake.right().unwrap()]);
-                    return (self, GameState::Playing);
-                }
-                (true, false) => {
-                    while !self.boneyard.is_empty() {
-                        let tile = self.boneyard.draw();
-                        current_player.hand_mut().add(tile);
-                        opponent.opponent_drew_tile();
-                        current_player.i_drew_tile(tile);
-                        if self.snake.is_playable(tile) {
-                            break;
-                        }
-                    }
-    
-                    if !Self::is_hand_playable(&current_player.hand(), &self.snake) {
-                        self.blocked_counter += 1;
-                        // player cannot be blocked if there aren't any tiles in the snake
-                        assert!(!self.snake.is_empty());
-                        opponent.opponent_was_blocked([self.snake.left().unwrap(), self.snake.right().unwrap()]);
-                        return (self, GameState::Playing);
-                    }
-                }
-                (false, _) => {}
-            };
-    
-            assert!(Self::is_hand_playable(&current_player.hand(), &self.snake));
-    
-            let tile = current_player.choose_tile(&self.snake);
-            self.snake.add(tile);
-
-            opponent.opponent_played_tile(tile);
-    
-            if current_player.hand().is_empty() {
-                return self.game_finished();
+        }
+
+        match self.players[seat].choose(&self.position.hands[seat], &self.position.snake) {
+            Choice::Play(mv) => self.apply(Action::Play(mv)),
+            Choice::TakeBack => {
+                self.take_back(seat);
+                return (self, GameState::Playing);
+            }
+            Choice::Quit => {
+                self.abandoned = true;
+                return (self, GameState::Playing);
             }
-    
-            self.current_player = (self.current_player + 1) % 2;
-            (self, GameState::Playing)
         }
+
+        let state = self.position.state();
+        if let GameState::Finished { .. } = state {
+            self.finish(&state);
+        }
+        (self, state)
     }
 
-    fn game_finished(self) -> (Self, GameState) {
-        let score_0 = self.players.0.hand().score();
-        let score_1 = self.players.1.hand().score();
+    /// Records the result and turns up every hand, once per result.
+    fn finish(&mut self, state: &GameState) {
+        if self.record.result.is_some() {
+            return;
+        }
+        self.record.game_finished(state);
+        for player in self.players.iter_mut() {
+            player.game_finished(state, &self.position.hands, &self.position.snake);
+        }
+    }
 
-        let winner = match score_0.cmp(&score_1) {
-            std::cmp::Ordering::Less => Some(Winner::Player0),
-            std::cmp::Ordering::Greater => Some(Winner::Player1),
-            std::cmp::Ordering::Equal => None,
+    /// Takes back the last draw, play or pass, see `Position::undo`.
+    pub fn undo(&mut self) -> bool {
+        let Some(event) = self.position.undo() else {
+            return false;
         };
+        self.record.events.pop();
+        self.record.result = None;
+        self.undone.push(event);
+        self.started = false;
+        true
+    }
 
-        // the winner gets the score of the opponent added to their score
-        let score_0 = match winner {
-            Some(Winner::Player0) => score_0 + score_1,
-            _ => score_0,
+    /// Makes the last undone event again, until the next turn is played.
+    pub fn redo(&mut self) -> bool {
+        let Some(event) = self.undone.pop() else {
+            return false;
         };
+        let event = self.position.apply(event.action());
+        self.record.push(event);
+        self.started = false;
+        true
+    }
 
-        let score_1 = match winner {
-            Some(Winner::Player1) => score_1 + score_0,
-            _ => score_1,
-        };
+    /// Undoes everything back to before `seat` last played a tile, so that it
+    /// is their turn again. Nothing happens when they haven't played yet.
+    pub fn take_back(&mut self, seat: usize) -> bool {
+        let is_their_play = |event: &Event| matches!(*event, Event::Play { seat: player, .. } if player == seat);
+        if !self.position.history.iter().any(|step| is_their_play(&step.event)) {
+            return false;
+        }
+        while let Some(step) = self.position.history.last() {
+            let done = is_their_play(&step.event);
+            self.undo();
+            if done {
+                break;
+            }
+        }
+        true
+    }
 
-        (
-            self,
-            GameState::Finished {
-                winner,
-                score_0,
-                score_1,
-            },
-        )
+    /// Tells every seat about the start of the game and everything since.
+    fn introduce(&mut self) {
+        let mut position = self.record.start.clone();
+        let hand_sizes: ArrayVec<usize, MAX_PLAYERS> = position.hands.iter().map(|hand| hand.tiles.len()).collect();
+        for (seat, player) in self.players.iter_mut().enumerate() {
+            let partner = position.partner(seat);
+            player.game_started(
+                seat,
+                &position.hands[seat],
+                &hand_sizes,
+                &position.snake,
+                position.game_mode,
+                partner,
+            );
+        }
+        for event in self.record.events.iter() {
+            position.apply(event.action());
+            Self::announce(&mut self.players, &position, *event);
+        }
     }
 
-    fn is_hand_playable(hand: &Hand, snake: &Snake) -> bool {
-        hand.tiles.iter().any(|tile| snake.is_playable(*tile))
+    /// Takes the action for the current player, records it and tells every
+    /// seat about it.
+    fn apply(&mut self, action: Action) {
+        let event = self.position.apply(action);
+        self.record.push(event);
+        Self::announce(&mut self.players, &self.position, event);
+    }
+
+    /// `position` is the one right after the event.
+    fn announce(players: &mut [Box<dyn Player>], position: &Position, event: Event) {
+        let seat = event.seat();
+        let partner = position.partner(seat);
+        let ends = position.snake.left().zip(position.snake.right());
+        for (other, player) in players.iter_mut().enumerate() {
+            if other == seat {
+                continue;
+            }
+            let is_partner = Some(other) == partner;
+            match event {
+                Event::Draw { .. } if is_partner => player.partner_drew_tile(seat),
+                Event::Draw { .. } => player.opponent_drew_tile(seat),
+                Event::Play { mv, .. } if is_partner => player.partner_played_tile(seat, mv.tile),
+                Event::Play { mv, .. } => player.opponent_played_tile(seat, mv.tile),
+                Event::Pass { .. } => {
+                    let (left, right) = ends.expect("passes only happen on a laid out snake");
+                    if is_partner {
+                        player.partner_was_blocked(seat, [left, right]);
+                    } else {
+                        player.opponent_was_blocked(seat, [left, right]);
+                    }
+                }
+            }
+        }
+        if let Event::Draw { tile, .. } = event {
+            players[seat].i_drew_tile(tile);
+        }
     }
 
     pub fn swap_players(mut self, should_swap: bool) -> Self {
         if should_swap {
-            self.current_player = (self.current_player + 1) % 2;
+            self.position.current_player = (self.position.current_player + 1) % self.players.len();
+            self.record.start.current_player = self.position.current_player;
         }
         self
     }
 }
 
-impl<'a, P1: Player, P2: Player> Debug for Game<P1, P2> {
+impl Debug for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let tiles_unicode_snake = [
-            ['🀱', '🀲', '🀳', '🀴', '🀵', '🀶', '🀷'],
-            ['🀸', '🀹', '🀺', '🀻', '🀼', '🀽', '🀾'],
-            ['🀿', '🁀', '🁁', '🁂', '🁃', '🁄', '🁅'],
-            ['🁆', '🁇', '🁈', '🁉', '🁊', '🁋', '🁌'],
-            ['🁍', '🁎', '🁏', '🁐', '🁑', '🁒', '🁓'],
-            ['🁔', '🁕', '🁖', '🁗', '🁘', '🁙', '🁚'],
-            ['🁛', '🁜', '🁝', '🁞', '🁟', '🁠', '🁡'],
-        ];
-
-        let tiles_unicode_hand = [
-            ['🁣', '🁤', '🁥', '🁦', '🁧', '🁨', '🁩'],
-            ['🁪', '🁫', '🁬', '🁭', '🁮', '🁯', '🁰'],
-            ['🁱', '🁲', '🁳', '🁴', '🁵', '🁶', '🁷'],
-            ['🁸', '🁹', '🁺', '🁻', '🁼', '🁽', '🁾'],
-            ['🁿', '🂀', '🂁', '🂂', '🂃', '🂄', '🂅'],
-            ['🂆', '🂇', '🂈', '🂉', '🂊', '🂋', '🂌'],
-            ['🂍', '🂎', '🂏', '🂐', '🂑', '🂒', '🂓'],
-        ];
-
         let print_hand = |hand: &Hand| {
             let mut result = String::new();
             for tile in hand.tiles.iter() {
-                result.push_str(&format!(
-                    " {}",
-                    tiles_unicode_hand[tile.left as usize][tile.right as usize]
-                ));
+                result.push_str(&format!(" {}", tile.vertical_glyph()));
             }
             result
         };
@@ -257,20 +386,540 @@ impl<'a, P1: Player, P2: Player> Debug for Game<P1, P2> {
         let print_snake = |snake: &Snake| {
             let mut result = String::new();
             for tile in snake.tiles.iter() {
-                result.push_str(&format!(
-                    " {}",
-                    tiles_unicode_snake[tile.left as usize][tile.right as usize]
-                ));
+                result.push_str(&format!(" {}", tile.horizontal_glyph()));
             }
             result
         };
 
-        write!(
-            f,
-            "Hand 0: {}\nSnake {}\nHand 1: {}",
-            print_hand(&self.players.0.hand()),
-            print_snake(&self.snake),
-            print_hand(&self.players.1.hand())
-        )
+        write!(f, "Snake {}", print_snake(&self.position.snake))?;
+        for (seat, hand) in self.position.hands.iter().enumerate() {
+            write!(f, "\nHand {}: {}", seat, print_hand(hand))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use arrayvec::ArrayVec;
+
+    use super::{Game, GameMode, GameState, Winner};
+    use crate::{
+        position::{fixtures, Position, PositionError},
+        choose_tile_strategy::{Choice, ChooseTileStrategy},
+        game_move::{Move, Side},
+        game_observer::GameObserver,
+        hand::Hand,
+        player::Player,
+        snake::Snake,
+        tile::Tile,
+    };
+
+    /// What a `TestPlayer` observed, shared with the test since the game owns the player.
+    #[derive(Default)]
+    struct Log {
+        started: Option<(usize, usize, Option<usize>)>,
+        drawn: Vec<Tile>,
+        opponent_draws: Vec<usize>,
+        opponent_plays: Vec<(usize, Tile)>,
+        opponent_blocks: Vec<(usize, [u8; 2])>,
+        partner_plays: Vec<(usize, Tile)>,
+        /// the hands turned up at every end of the game
+        finished: Vec<Vec<usize>>,
+        /// answer `Choice::TakeBack` on the next turn
+        take_back: bool,
+        /// answer `Choice::Quit` on the next turn
+        quit: bool,
+    }
+
+    #[derive(Default)]
+    struct TestPlayer {
+        log: Rc<RefCell<Log>>,
+    }
+
+    impl Player for TestPlayer {}
+
+    impl ChooseTileStrategy for TestPlayer {
+        fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+            let tile = *hand.tiles.iter().find(|tile| snake.is_playable(**tile)).unwrap();
+            let side = if snake.fits(tile, Side::Right) { Side::Right } else { Side::Left };
+            Move::new(tile, side)
+        }
+
+        fn choose(&mut self, hand: &Hand, snake: &Snake) -> Choice {
+            if std::mem::take(&mut self.log.borrow_mut().take_back) {
+                return Choice::TakeBack;
+            }
+            if self.log.borrow().quit {
+                return Choice::Quit;
+            }
+            Choice::Play(self.choose_move(hand, snake))
+        }
+    }
+
+    impl GameObserver for TestPlayer {
+        fn game_started(
+            &mut self,
+            seat: usize,
+            _hand: &Hand,
+            hand_sizes: &[usize],
+            _snake: &Snake,
+            _game_mode: GameMode,
+            partner: Option<usize>,
+        ) {
+            // told again from the start after a take back
+            *self.log.borrow_mut() = Log {
+                started: Some((seat, hand_sizes.len(), partner)),
+                ..Log::default()
+            };
+        }
+
+        fn opponent_drew_tile(&mut self, seat: usize) {
+            self.log.borrow_mut().opponent_draws.push(seat);
+        }
+
+        fn i_drew_tile(&mut self, tile: Tile) {
+            self.log.borrow_mut().drawn.push(tile);
+        }
+
+        fn opponent_played_tile(&mut self, seat: usize, tile: Tile) {
+            self.log.borrow_mut().opponent_plays.push((seat, tile));
+        }
+
+        fn opponent_was_blocked(&mut self, seat: usize, pips: [u8; 2]) {
+            self.log.borrow_mut().opponent_blocks.push((seat, pips));
+        }
+
+        fn partner_played_tile(&mut self, seat: usize, tile: Tile) {
+            self.log.borrow_mut().partner_plays.push((seat, tile));
+        }
+
+        fn game_finished(&mut self, _state: &GameState, hands: &[Hand], _snake: &Snake) {
+            let sizes = hands.iter().map(|hand| hand.tiles.len()).collect();
+            self.log.borrow_mut().finished.push(sizes);
+        }
+    }
+
+    fn tiles(tiles: &[(u8, u8)]) -> ArrayVec<Tile, 28> {
+        tiles.iter().map(|&(left, right)| Tile::new(left, right)).collect()
+    }
+
+    /// Seat 0 is to move on a snake of [3|5]; the boneyard is drawn from the back.
+    fn game(mode: GameMode, hands: &[&[(u8, u8)]], boneyard: &[(u8, u8)]) -> (Game, Vec<Rc<RefCell<Log>>>) {
+        let mut players: Vec<Box<dyn Player>> = Vec::new();
+        let mut logs = Vec::new();
+        for _ in hands {
+            let player = TestPlayer::default();
+            logs.push(player.log.clone());
+            players.push(Box::new(player));
+        }
+
+        let mut position = fixtures::position(hands, &[(3, 5)]);
+        position.boneyard.tiles = tiles(boneyard);
+        let game = Game::from_position(position, players).unwrap().game_mode(mode);
+        (game, logs)
+    }
+
+    fn hand(game: &Game, seat: usize) -> &[Tile] {
+        game.hand(seat).tiles.as_slice()
+    }
+
+    #[test]
+    fn draw_mode_draws_until_a_tile_is_playable() {
+        let (game, logs) = game(GameMode::Draw, &[&[(0, 1)], &[(6, 6), (4, 4)]], &[(6, 4), (5, 2), (1, 1)]);
+
+        let (game, _) = game.play();
+
+        assert_eq!(logs[0].borrow().drawn, vec![Tile::new(1, 1), Tile::new(5, 2)]);
+        assert_eq!(logs[1].borrow().opponent_draws, vec![0, 0]);
+        assert!(logs[1].borrow().opponent_blocks.is_empty());
+        assert_eq!(game.position.boneyard.tiles.as_slice(), &[Tile::new(6, 4)]);
+        assert_eq!(game.position.snake.tiles.len(), 2);
+        assert_eq!(hand(&game, 0), &[Tile::new(0, 1), Tile::new(1, 1)]);
+        assert_eq!(game.position.current_player, 1);
+        assert_eq!(game.position.blocked_counter, 0);
+    }
+
+    #[test]
+    fn draw_mode_passes_once_the_boneyard_is_empty() {
+        let (game, logs) = game(GameMode::Draw, &[&[(0, 1)], &[(6, 6), (4, 4)]], &[(6, 4), (1, 1)]);
+
+        let (game, _) = game.play();
+
+        assert_eq!(logs[0].borrow().drawn, vec![Tile::new(1, 1), Tile::new(6, 4)]);
+        assert_eq!(logs[1].borrow().opponent_draws, vec![0, 0]);
+        assert_eq!(logs[1].borrow().opponent_blocks, vec![(0, [3, 5])]);
+        assert!(game.position.boneyard.is_empty());
+        assert_eq!(game.position.blocked_counter, 1);
+    }
+
+    #[test]
+    fn block_mode_never_draws() {
+        let (game, logs) = game(GameMode::Block, &[&[(0, 1)], &[(6, 6), (4, 4)]], &[(6, 4), (5, 2)]);
+
+        let (game, _) = game.play();
+
+        assert!(logs[0].borrow().drawn.is_empty());
+        assert!(logs[1].borrow().opponent_draws.is_empty());
+        assert_eq!(logs[1].borrow().opponent_blocks, vec![(0, [3, 5])]);
+        assert_eq!(game.position.boneyard.tiles.len(), 2);
+        assert_eq!(game.position.blocked_counter, 1);
+        assert_eq!(game.position.current_player, 1);
+    }
+
+    #[test]
+    fn playing_a_tile_resets_the_blocked_counter() {
+        let (game, logs) = game(GameMode::Block, &[&[(0, 1)], &[(5, 0), (6, 6)]], &[]);
+
+        let (game, _) = game.play();
+        assert_eq!(game.position.blocked_counter, 1);
+
+        let (game, state) = game.play();
+        assert!(matches!(state, GameState::Playing));
+        assert_eq!(game.position.blocked_counter, 0);
+
+        // [0|1] now fits the [5|0] end, so seat 0 is no longer blocked
+        let (game, _) = game.play();
+        assert_eq!(game.position.blocked_counter, 0);
+        assert_eq!(game.position.snake.tiles.len(), 3);
+        assert_eq!(logs[1].borrow().opponent_blocks, vec![(0, [3, 5])]);
+    }
+
+    #[test]
+    fn a_game_starts_from_the_given_position() {
+        let hands: [&[(u8, u8)]; 3] = [&[(0, 1), (6, 6)], &[(6, 4)], &[(2, 2), (3, 3), (4, 4)]];
+        let mut position = fixtures::position(&hands, &[(2, 1), (1, 6)]);
+        position.boneyard.tiles.extend(tiles(&[(5, 5)]));
+        position.current_player = 1;
+        let players = (0..3)
+            .map(|_| Box::new(TestPlayer::default()) as Box<dyn Player>)
+            .collect();
+
+        let (game, state) = Game::from_position(position, players).unwrap().play();
+
+        // seat 1 plays [6|4] on the right and goes out
+        assert!(matches!(state, GameState::Finished { winner: Some(Winner::Seat(1)), .. }));
+        assert_eq!(game.snake().right(), Some(4));
+        assert_eq!(game.hand(0), &fixtures::hand(&[(0, 1), (6, 6)]));
+        assert_eq!(game.boneyard().tiles.len(), 1);
+    }
+
+    #[test]
+    fn a_position_needs_a_hand_for_every_player() {
+        let position = Position::deal(&mut rand::thread_rng(), 3);
+        let players = (0..2)
+            .map(|_| Box::new(TestPlayer::default()) as Box<dyn Player>)
+            .collect();
+
+        let error = Game::from_position(position, players).err();
+        assert_eq!(error, Some(PositionError::SeatCount { hands: 3, players: 2 }));
+    }
+
+    #[test]
+    fn deal_size_depends_on_player_count() {
+        for (player_count, hand_size) in [(2, 7), (3, 5), (4, 5)] {
+            let players = (0..player_count)
+                .map(|_| Box::new(TestPlayer::default()) as Box<dyn Player>)
+                .collect();
+            let game = Game::new(&mut rand::thread_rng(), players);
+
+            assert_eq!(game.players.len(), player_count);
+            for seat in 0..player_count {
+                assert_eq!(hand(&game, seat).len(), hand_size);
+            }
+            assert_eq!(game.position.boneyard.tiles.len(), 28 - player_count * hand_size);
+        }
+    }
+
+    #[test]
+    fn a_seed_determines_the_deal() {
+        let deal = |seed| {
+            let players = (0..3)
+                .map(|_| Box::new(TestPlayer::default()) as Box<dyn Player>)
+                .collect();
+            let game = Game::from_seed(seed, players);
+            let hands: Vec<Vec<Tile>> = (0..3).map(|seat| hand(&game, seat).to_vec()).collect();
+            (hands, game.position.boneyard)
+        };
+
+        assert!(deal(42) == deal(42));
+        assert!(deal(42) != deal(43));
+    }
+
+    #[test]
+    fn a_snapshot_picks_the_game_back_up() {
+        let (game, _) = game(GameMode::Draw, &[&[(0, 1)], &[(5, 0), (6, 6)]], &[(6, 4), (1, 1)]);
+        let (game, _) = game.play();
+
+        let snapshot = game.snapshot();
+        let players = (0..2)
+            .map(|_| Box::new(TestPlayer::default()) as Box<dyn Player>)
+            .collect();
+        let restored = Game::from_snapshot(snapshot.clone(), players).unwrap();
+
+        assert_eq!(restored.snapshot(), snapshot);
+        assert_eq!(restored.current_player(), 1);
+        // seat 0 drew both tiles and still had to pass
+        assert_eq!(restored.hand(0).tiles.len(), 3);
+        assert_eq!(restored.record().events.len(), 3);
+        assert_eq!(restored.position().blocked_counter, 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn a_snapshot_survives_json() {
+        let (game, _) = game(GameMode::Draw, &[&[(0, 1)], &[(5, 0), (6, 6)]], &[(6, 4), (1, 1)]);
+        let (game, _) = game.play();
+        let snapshot = game.snapshot();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let loaded: super::GameSnapshot = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded, snapshot);
+    }
+
+    #[test]
+    fn games_finish_with_every_player_count() {
+        for player_count in 2..=4 {
+            for mode in [GameMode::Block, GameMode::Draw] {
+                let players = (0..player_count)
+                    .map(|_| Box::new(TestPlayer::default()) as Box<dyn Player>)
+                    .collect();
+                let mut game = Game::new(&mut rand::thread_rng(), players).game_mode(mode);
+
+                let scores = loop {
+                    let (next, state) = game.play();
+                    game = next;
+                    if let GameState::Finished { scores, .. } = state {
+                        break scores;
+                    }
+                };
+                assert_eq!(scores.len(), player_count);
+            }
+        }
+    }
+
+    #[test]
+    fn turns_rotate_through_every_seat() {
+        let (game, logs) = game(
+            GameMode::Block,
+            &[&[(5, 1), (0, 0)], &[(1, 2), (0, 6)], &[(6, 6), (4, 4)], &[(2, 4), (0, 5)]],
+            &[],
+        );
+
+        let (game, _) = game.play();
+        assert_eq!(game.position.current_player, 1);
+        let (game, _) = game.play();
+        assert_eq!(game.position.current_player, 2);
+        // seat 2 has nothing on [3|5|1|2] and passes
+        let (game, _) = game.play();
+        assert_eq!(game.position.current_player, 3);
+        let (game, _) = game.play();
+        assert_eq!(game.position.current_player, 0);
+
+        assert_eq!(
+            logs[0].borrow().opponent_plays,
+            vec![(1, Tile::new(1, 2)), (3, Tile::new(2, 4))]
+        );
+        assert_eq!(logs[0].borrow().opponent_blocks, vec![(2, [3, 2])]);
+        assert_eq!(logs[2].borrow().opponent_plays.len(), 3);
+        assert!(logs[2].borrow().opponent_blocks.is_empty());
+    }
+
+    #[test]
+    fn game_is_blocked_once_every_seat_passes() {
+        let (mut game, _) = game(GameMode::Block, &[&[(0, 0)], &[(1, 1)], &[(2, 2)]], &[]);
+
+        for blocked_counter in 1..=3 {
+            let (next, state) = game.play();
+            game = next;
+            assert!(matches!(state, GameState::Playing));
+            assert_eq!(game.position.blocked_counter, blocked_counter);
+        }
+
+        let (_, state) = game.play();
+        let GameState::Finished { winner, scores } = state else {
+            panic!("the game should be blocked");
+        };
+        // the lowest hand takes every pip on the table
+        assert_eq!(winner, Some(Winner::Seat(0)));
+        assert_eq!(scores.as_slice(), &[6, 2, 4]);
+    }
+
+    #[test]
+    fn every_seat_sees_the_hands_once_the_game_is_over() {
+        let (game, logs) = game(GameMode::Block, &[&[(5, 6)], &[(0, 0), (1, 2)]], &[]);
+
+        let (game, _) = game.play();
+        // asking a finished game again tells nobody twice
+        let (_, state) = game.play();
+        assert!(matches!(state, GameState::Finished { .. }));
+        for log in logs {
+            assert_eq!(log.borrow().finished, vec![vec![0, 2]]);
+        }
+    }
+
+    #[test]
+    fn tied_lowest_hands_have_no_winner() {
+        let (mut game, _) = game(GameMode::Block, &[&[(1, 1)], &[(0, 2)], &[(6, 6)]], &[]);
+        game.position.blocked_counter = 3;
+
+        let (_, state) = game.play();
+        let GameState::Finished { winner, scores } = state else {
+            unreachable!();
+        };
+        assert_eq!(winner, None);
+        assert_eq!(scores.as_slice(), &[2, 2, 12]);
+    }
+
+    #[test]
+    fn going_out_wins_even_with_a_blank_hand_left() {
+        let (game, _) = game(GameMode::Block, &[&[(5, 6)], &[(0, 0)]], &[]);
+
+        let (_, state) = game.play();
+        let GameState::Finished { winner, scores } = state else {
+            panic!("seat 0 went out");
+        };
+        assert_eq!(winner, Some(Winner::Seat(0)));
+        assert_eq!(scores.as_slice(), &[0, 0]);
+    }
+
+    #[test]
+    fn partners_are_told_about_each_other() {
+        let (game, logs) = game(
+            GameMode::Block,
+            &[&[(5, 1), (0, 0)], &[(6, 6)], &[(4, 4)], &[(2, 2)]],
+            &[],
+        );
+
+        let (game, _) = game.partnership(true).play();
+
+        assert_eq!(logs[0].borrow().started, Some((0, 4, Some(2))));
+        assert_eq!(logs[3].borrow().started, Some((3, 4, Some(1))));
+        assert_eq!(logs[2].borrow().partner_plays, vec![(0, Tile::new(5, 1))]);
+        assert!(logs[2].borrow().opponent_plays.is_empty());
+        for seat in [1, 3] {
+            assert_eq!(logs[seat].borrow().opponent_plays, vec![(0, Tile::new(5, 1))]);
+            assert!(logs[seat].borrow().partner_plays.is_empty());
+        }
+        assert_eq!(game.position.current_player, 1);
+    }
+
+    #[test]
+    fn without_partnership_nobody_has_a_partner() {
+        let (game, logs) = game(GameMode::Block, &[&[(5, 1)], &[(6, 6)], &[(4, 4)], &[(2, 2)]], &[]);
+
+        let _ = game.play();
+
+        assert_eq!(logs[2].borrow().started, Some((2, 4, None)));
+        assert_eq!(logs[2].borrow().opponent_plays, vec![(0, Tile::new(5, 1))]);
+    }
+
+    #[test]
+    fn a_team_wins_when_either_partner_goes_out() {
+        let (game, _) = game(
+            GameMode::Block,
+            &[&[(5, 1)], &[(0, 0)], &[(6, 6), (6, 5)], &[(0, 1)]],
+            &[],
+        );
+
+        let (_, state) = game.partnership(true).play();
+        let GameState::Finished { winner, scores } = state else {
+            panic!("seat 0 went out");
+        };
+        // team 0 still holds more pips, but seat 0 went out
+        assert_eq!(winner, Some(Winner::Team(0)));
+        assert_eq!(scores.as_slice(), &[24, 1, 24, 1]);
+    }
+
+    #[test]
+    fn a_blocked_partnership_game_compares_team_totals() {
+        let (game, _) = game(
+            GameMode::Block,
+            &[&[(0, 1)], &[(0, 0)], &[(1, 1)], &[(2, 2)]],
+            &[],
+        );
+        let mut game = game.partnership(true);
+        game.position.blocked_counter = 4;
+
+        // seat 1 holds the lightest hand, but team 0 holds fewer pips together
+        let (_, state) = game.play();
+        let GameState::Finished { winner, scores } = state else {
+            unreachable!();
+        };
+        assert_eq!(winner, Some(Winner::Team(0)));
+        assert!(winner.unwrap().contains(2));
+        assert!(!winner.unwrap().contains(1));
+        assert_eq!(scores.as_slice(), &[7, 4, 7, 4]);
+    }
+
+    #[test]
+    fn undo_and_redo_step_through_the_whole_game() {
+        let (mut game, _) = game(
+            GameMode::Draw,
+            &[&[(5, 1), (0, 0), (2, 4)], &[(1, 4), (6, 6)]],
+            &[(0, 3), (4, 4), (6, 2)],
+        );
+        let start = game.position.clone();
+        loop {
+            let (next, state) = game.play();
+            game = next;
+            if let GameState::Finished { .. } = state {
+                break;
+            }
+        }
+        let end = game.snapshot();
+
+        while game.undo() {}
+        assert_eq!(game.position, start);
+        assert!(game.record().events.is_empty());
+        assert_eq!(game.record().result, None);
+
+        while game.redo() {}
+        assert_eq!(game.position, end.position);
+        assert_eq!(game.record().events, end.record.events);
+    }
+
+    #[test]
+    fn a_take_back_hands_the_turn_back() {
+        let (game, logs) = game(GameMode::Block, &[&[(5, 1), (4, 0)], &[(1, 4), (6, 6)]], &[]);
+        let (game, _) = game.play();
+        let (game, _) = game.play();
+        assert_eq!(game.snake().right(), Some(4));
+
+        logs[0].borrow_mut().take_back = true;
+        let (game, state) = game.play();
+
+        // both tiles played since seat 0's last turn are back in the hands
+        assert_eq!(state, GameState::Playing);
+        assert_eq!(game.current_player(), 0);
+        assert_eq!(hand(&game, 0), &[Tile::new(5, 1), Tile::new(4, 0)]);
+        assert_eq!(hand(&game, 1), &[Tile::new(1, 4), Tile::new(6, 6)]);
+        assert_eq!(game.snake().tiles.len(), 1);
+        assert!(game.record().events.is_empty());
+
+        // nothing is left to take back, and the players hear it all over again
+        let mut game = game;
+        assert!(!game.take_back(0));
+        let _ = game.play();
+        assert_eq!(logs[1].borrow().opponent_plays, vec![(0, Tile::new(5, 1))]);
+    }
+
+    #[test]
+    fn quitting_leaves_the_game_where_it_is() {
+        let (game, logs) = game(GameMode::Block, &[&[(5, 1), (4, 0)], &[(1, 4), (6, 6)]], &[]);
+        let (game, _) = game.play();
+        logs[1].borrow_mut().quit = true;
+        let (game, state) = game.play();
+
+        assert_eq!(state, GameState::Playing);
+        assert!(game.abandoned());
+        let events = game.record().events.clone();
+        let (game, _) = game.play();
+        assert_eq!(game.record().events, events);
+        assert_eq!(game.current_player(), 1);
+        assert_eq!(game.record().result, None);
     }
 }
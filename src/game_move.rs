@@ -0,0 +1,129 @@
+use std::fmt;
+
+use arrayvec::ArrayVec;
+
+use crate::{hand::Hand, snake::Snake, tile::Tile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// A tile from the hand and the end of the snake it goes on. On an empty
+/// snake either side starts the line.
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    pub tile: Tile,
+    pub side: Side,
+}
+
+impl Move {
+    pub fn new(tile: Tile, side: Side) -> Move {
+        Move { tile, side }
+    }
+}
+
+impl fmt::Debug for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {:?}", self.tile, self.side)
+    }
+}
+
+/// Every tile in a hand could fit both ends.
+pub const MAX_MOVES: usize = 42;
+
+/// What the player to move may do: play a tile, or, only when no tile fits,
+/// draw from the boneyard or pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Action {
+    Play(Move),
+    Draw,
+    Pass,
+}
+
+/// Every way to put a tile from the hand on the snake. Playing on either
+/// side of an empty snake, or of a snake with the same pip on both ends,
+/// leaves the same ends, so those tiles are listed once, on the right.
+pub fn legal_moves(hand: &Hand, snake: &Snake) -> ArrayVec<Move, MAX_MOVES> {
+    let same_ends = snake.left() == snake.right();
+    let mut moves = ArrayVec::new();
+    for tile in hand.tiles.iter() {
+        if !same_ends && snake.fits(*tile, Side::Left) {
+            moves.push(Move::new(*tile, Side::Left));
+        }
+        if snake.fits(*tile, Side::Right) {
+            moves.push(Move::new(*tile, Side::Right));
+        }
+    }
+    moves
+}
+
+/// `can_draw` is whether the rules let the player draw instead of passing,
+/// i.e. a draw game with tiles left in the boneyard.
+pub fn legal_actions(hand: &Hand, snake: &Snake, can_draw: bool) -> ArrayVec<Action, MAX_MOVES> {
+    let mut actions: ArrayVec<Action, MAX_MOVES> =
+        legal_moves(hand, snake).into_iter().map(Action::Play).collect();
+    if actions.is_empty() {
+        actions.push(if can_draw { Action::Draw } else { Action::Pass });
+    }
+    actions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{legal_actions, legal_moves, Action, Move, Side};
+    use crate::{position::fixtures::hand, snake::Snake, tile::Tile};
+
+    fn snake(tiles: &[(u8, u8)]) -> Snake {
+        let mut snake = Snake::new();
+        for &(left, right) in tiles {
+            snake.play(Tile::new(left, right), Side::Right).unwrap();
+        }
+        snake
+    }
+
+    #[test]
+    fn lists_every_tile_and_side_that_fits() {
+        let moves = legal_moves(&hand(&[(3, 5), (5, 6), (0, 1), (3, 3)]), &snake(&[(3, 5)]));
+
+        assert_eq!(
+            moves.as_slice(),
+            &[
+                Move::new(Tile::new(3, 5), Side::Left),
+                Move::new(Tile::new(3, 5), Side::Right),
+                Move::new(Tile::new(5, 6), Side::Right),
+                Move::new(Tile::new(3, 3), Side::Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_tiles_once_when_the_side_makes_no_difference() {
+        let tiles = hand(&[(3, 5), (6, 6)]);
+
+        let on_empty_snake = legal_moves(&tiles, &Snake::new());
+        assert_eq!(
+            on_empty_snake.as_slice(),
+            &[Move::new(Tile::new(3, 5), Side::Right), Move::new(Tile::new(6, 6), Side::Right)]
+        );
+
+        let on_same_ends = legal_moves(&tiles, &snake(&[(3, 6), (6, 3)]));
+        assert_eq!(on_same_ends.as_slice(), &[Move::new(Tile::new(3, 5), Side::Right)]);
+    }
+
+    #[test]
+    fn draws_or_passes_only_when_nothing_fits() {
+        let snake = snake(&[(3, 5)]);
+
+        let playable = legal_actions(&hand(&[(0, 1), (5, 5)]), &snake, true);
+        assert_eq!(playable.as_slice(), &[Action::Play(Move::new(Tile::new(5, 5), Side::Right))]);
+
+        let stuck = hand(&[(0, 1)]);
+        assert_eq!(legal_actions(&stuck, &snake, true).as_slice(), &[Action::Draw]);
+        assert_eq!(legal_actions(&stuck, &snake, false).as_slice(), &[Action::Pass]);
+    }
+}
@@ -1,9 +1,29 @@
-use crate::{tile::Tile, snake::Snake};
+use crate::{game::{GameMode, GameState}, hand::Hand, snake::Snake, tile::Tile};
 
+/// Seats are numbered from 0 in turn order; `seat` is always the seat of the
+/// other player the event is about.
 pub trait GameObserver {
-	fn game_started(&mut self) {}
-	fn opponent_drew_tile(&mut self) {}
-	fn i_drew_tile(&mut self, tile: Tile) {}
-	fn opponent_played_tile(&mut self, tile: Tile) {}
-	fn opponent_was_blocked(&mut self, pips: [u8;2]) {}
+	/// `hand` is my own, `hand_sizes` is indexed by seat. The snake is only
+	/// laid out already when the game started from a custom position.
+	/// `partner` is only set for partnership play
+	fn game_started(&mut self, _seat: usize, _hand: &Hand, _hand_sizes: &[usize], _snake: &Snake, _game_mode: GameMode, _partner: Option<usize>) {}
+	fn opponent_drew_tile(&mut self, _seat: usize) {}
+	fn i_drew_tile(&mut self, _tile: Tile) {}
+	fn opponent_played_tile(&mut self, _seat: usize, _tile: Tile) {}
+	fn opponent_was_blocked(&mut self, _seat: usize, _pips: [u8;2]) {}
+	/// Every hand is turned up once the game is over, `hands` is indexed
+	/// by seat. Only called again if the game was taken back and finished
+	/// anew.
+	fn game_finished(&mut self, _state: &GameState, _hands: &[Hand], _snake: &Snake) {}
+
+	// events about my partner, observed like any opponent unless overridden
+	fn partner_drew_tile(&mut self, seat: usize) {
+		self.opponent_drew_tile(seat);
+	}
+	fn partner_played_tile(&mut self, seat: usize, tile: Tile) {
+		self.opponent_played_tile(seat, tile);
+	}
+	fn partner_was_blocked(&mut self, seat: usize, pips: [u8;2]) {
+		self.opponent_was_blocked(seat, pips);
+	}
 }
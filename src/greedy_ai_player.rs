@@ -1,25 +1,13 @@
-use crate::{hand::{Hand, HasHandTrait}, snake::Snake, tile::Tile, player::Player, choose_tile_strategy::ChooseTileStrategy, game_observer::GameObserver};
+use crate::{hand::Hand, snake::Snake, player::Player, choose_tile_strategy::ChooseTileStrategy, game_move::{legal_moves, Move}, game_observer::GameObserver};
 
 #[derive(Default)]
-pub struct GreedyAIPlayer {
-	pub hand: Hand,
-}
+pub struct GreedyAIPlayer;
 
 impl Player for GreedyAIPlayer {}
 
-impl HasHandTrait for GreedyAIPlayer {
-    fn hand(&self) -> &Hand {
-        &self.hand
-    }
-    fn hand_mut(&mut self) -> &mut Hand {
-        &mut self.hand
-    }
-}
-
 impl ChooseTileStrategy for  GreedyAIPlayer {
-    fn choose_tile(&mut self, snake: &Snake) -> Tile {
-        let tile_index = self.hand.tiles.iter().filter(|tile| snake.is_playable(**tile)).enumerate().max_by_key(|(_, tile)| tile.score()).unwrap().0;
-        self.hand.tiles.remove(tile_index)
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        legal_moves(hand, snake).into_iter().max_by_key(|mv| mv.tile.score()).unwrap()
     }
 }
 
@@ -1,6 +1,6 @@
 use arrayvec::ArrayVec;
 
-use crate::{tile::Tile, boneyard::{Boneyard, self}, snake::Snake};
+use crate::tile::Tile;
 
 pub trait TilesTrait {
     fn tiles(&self) -> &ArrayVec<Tile, 21>;
@@ -16,6 +16,17 @@ pub trait HandTrait: TilesTrait {
         self.tiles_mut().push(tile);
     }
 
+    /// returns false if the tile is not in the hand
+    fn remove(&mut self, tile: Tile) -> bool {
+        match self.tiles().iter().position(|t| *t == tile) {
+            Some(index) => {
+                self.tiles_mut().remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
     fn add_multiple(&mut self, tiles: ArrayVec<Tile, 7>) {
         self.tiles_mut().extend(tiles);
     }
@@ -25,11 +36,8 @@ pub trait HandTrait: TilesTrait {
     }
 }
 
-pub trait HasHandTrait {
-    fn hand(&self) -> &Hand;
-    fn hand_mut(&mut self) -> &mut Hand;
-}
-
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Hand {
     pub tiles: ArrayVec<Tile, 21>,
 }
@@ -1,57 +1,75 @@
-use crate::{snake::Snake, tile::Tile, hand::{Hand, HasHandTrait}, choose_tile_strategy::ChooseTileStrategy, game_observer::GameObserver, player::Player};
+use crate::{snake::Snake, hand::Hand, choose_tile_strategy::{Choice, ChooseTileStrategy}, game_move::{legal_moves, Move, Side}, game_observer::GameObserver, player::Player};
 
 #[derive(Default)]
-pub struct HumanPlayer {
-	pub hand: Hand,
-}
+pub struct HumanPlayer;
 
-impl HasHandTrait for HumanPlayer {
-    fn hand(&self) -> &Hand {
-        &self.hand
-    }
-    fn hand_mut(&mut self) -> &mut Hand {
-        &mut self.hand
+impl ChooseTileStrategy for  HumanPlayer {
+	fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        loop {
+            match self.choose(hand, snake) {
+                Choice::Play(mv) => return mv,
+                Choice::TakeBack | Choice::Quit => println!("You have to play a tile now"),
+            }
+        }
     }
-}
 
-impl ChooseTileStrategy for  HumanPlayer {
-	fn choose_tile(&mut self, snake: &Snake) -> Tile {
+    fn choose(&mut self, hand: &Hand, snake: &Snake) -> Choice {
         // get console input from stdin
-        let mut input = String::new();
-        println!("Choose a tile from your hand");
-        while let Err(_) = std::io::stdin().read_line(&mut input) {
-            println!("Invalid input, try again");
-            input.clear();
+        println!("Choose a tile from your hand, or (u)ndo your last move");
+        let input = Self::read_line();
+        if input == "u" || input == "undo" {
+            return Choice::TakeBack;
         }
 
-        let index = input.trim().parse::<usize>();
-
-        let index = match index {
+        let index = match input.parse::<usize>() {
             Ok(index) => index,
             Err(_) => {
                 println!("Invalid input, try again");
-                return self.choose_tile(snake);
+                return self.choose(hand, snake);
             }
         };
 
-        let tile = self.hand.tiles.get(index).map(|f| *f);
+        let tile = hand.tiles.get(index).copied();
+        let moves: Vec<Move> = legal_moves(hand, snake)
+            .into_iter()
+            .filter(|mv| Some(mv.tile) == tile)
+            .collect();
 
-        let tile_is_playable = match tile {
-            Some(tile) => snake.is_playable(tile),
-            None => false,
+        let mv = match moves.as_slice() {
+            [] => {
+                println!("Invalid tile, try again");
+                return self.choose(hand, snake);
+            }
+            [mv] => *mv,
+            [_, _] => Move::new(tile.unwrap(), Self::choose_side()),
+            _ => unreachable!("a tile has two sides"),
         };
 
-        if !tile_is_playable {
-            println!("Invalid tile, try again");
-            return self.choose_tile(snake);
-        }
-
-        println!("You chose {:?}", tile);
+        println!("You chose {:?}", mv);
+        Choice::Play(mv)
+    }
+}
 
-        let tile = tile.unwrap();
+impl HumanPlayer {
+    fn read_line() -> String {
+        let mut input = String::new();
+        while std::io::stdin().read_line(&mut input).is_err() {
+            println!("Invalid input, try again");
+            input.clear();
+        }
+        input.trim().to_string()
+    }
 
-        let index = self.hand.tiles.iter().position(|t| *t == tile).unwrap();
-        self.hand.tiles.remove(index)
+    fn choose_side() -> Side {
+        println!("Play it on the (l)eft or (r)ight end?");
+        match Self::read_line().as_str() {
+            "l" | "left" => Side::Left,
+            "r" | "right" => Side::Right,
+            _ => {
+                println!("Invalid side, try again");
+                Self::choose_side()
+            }
+        }
     }
 }
 
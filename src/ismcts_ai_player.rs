@@ -0,0 +1,284 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    choose_tile_strategy::ChooseTileStrategy,
+    game::{GameMode, GameState},
+    game_move::{legal_moves, Action, Move},
+    game_observer::GameObserver,
+    hand::Hand,
+    knowledge::Knowledge,
+    mcts::{playout, reward, Budget, Clock},
+    player::Player,
+    position::Position,
+    snake::Snake,
+    solver::{Solution, Solver},
+    tile::Tile,
+};
+
+/// Plans with information set MCTS: a single tree over what the player can
+/// tell apart, walked with a fresh deal of the hidden tiles on every
+/// iteration. Unlike `MctsAIPlayer` it never plans a move that only works
+/// when the hidden tiles happen to lie one particular way.
+pub struct IsmctsAIPlayer {
+    budget: Budget,
+    exploration: f64,
+    rng: StdRng,
+    knowledge: Knowledge,
+    /// takes over once every tile is known
+    solver: Solver,
+}
+
+impl Default for IsmctsAIPlayer {
+    fn default() -> Self {
+        Self::new(Budget::Iterations(1000))
+    }
+}
+
+impl IsmctsAIPlayer {
+    pub fn new(budget: Budget) -> Self {
+        IsmctsAIPlayer {
+            budget,
+            exploration: 0.7,
+            rng: StdRng::from_entropy(),
+            knowledge: Knowledge::default(),
+            solver: Solver::new(),
+        }
+    }
+
+    /// The UCB1 constant, higher tries unpromising moves more often.
+    pub fn exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    /// Makes the searches repeatable.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+struct Node {
+    /// `None` for the root
+    action: Option<Action>,
+    /// the seat that took `action`, whose reward the node keeps
+    actor: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    visits: u32,
+    /// how often the action was legal when its parent was visited
+    availability: u32,
+    reward: f64,
+}
+
+/// Nodes are reached by actions alone, so one node stands for every deal in
+/// which the same actions were taken.
+struct InfoSetTree {
+    nodes: Vec<Node>,
+    exploration: f64,
+}
+
+impl InfoSetTree {
+    fn new(seat: usize, exploration: f64) -> InfoSetTree {
+        let root = Node {
+            action: None,
+            actor: seat,
+            parent: None,
+            children: Vec::new(),
+            visits: 0,
+            availability: 0,
+            reward: 0.0,
+        };
+        InfoSetTree {
+            nodes: vec![root],
+            exploration,
+        }
+    }
+
+    /// Walks the tree along the actions legal in `deal`, expands one that
+    /// wasn't tried yet, plays out and backs up. `deal` is left as it was.
+    fn iterate<R: Rng + ?Sized>(&mut self, deal: &mut Position, rng: &mut R) {
+        let start = deal.history.len();
+
+        let mut node = 0;
+        while deal.state() == GameState::Playing {
+            let mut untried = deal.legal_actions();
+            let mut legal = Vec::new();
+            for &child in self.nodes[node].children.iter() {
+                if let Some(index) = untried.iter().position(|action| Some(*action) == self.nodes[child].action) {
+                    untried.swap_remove(index);
+                    legal.push(child);
+                }
+            }
+            for &child in legal.iter() {
+                self.nodes[child].availability += 1;
+            }
+
+            if !untried.is_empty() {
+                let action = untried[rng.gen_range(0..untried.len())];
+                let actor = deal.current_player;
+                deal.apply(action);
+
+                let child = self.nodes.len();
+                self.nodes.push(Node {
+                    action: Some(action),
+                    actor,
+                    parent: Some(node),
+                    children: Vec::new(),
+                    visits: 0,
+                    availability: 1,
+                    reward: 0.0,
+                });
+                self.nodes[node].children.push(child);
+                node = child;
+                break;
+            }
+
+            node = self.select(&legal);
+            deal.apply(self.nodes[node].action.unwrap());
+        }
+
+        let state = playout(deal, rng);
+
+        let mut next = Some(node);
+        while let Some(node) = next {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.reward += reward(&state, node.actor);
+            next = node.parent;
+        }
+
+        while deal.history.len() > start {
+            deal.undo();
+        }
+    }
+
+    /// UCB1 with the parent's visits replaced by how often the child was
+    /// available.
+    fn select(&self, legal: &[usize]) -> usize {
+        let score = |child: &usize| {
+            let child = &self.nodes[*child];
+            child.reward / child.visits as f64
+                + self.exploration * ((child.availability as f64).ln() / child.visits as f64).sqrt()
+        };
+        *legal.iter().max_by(|a, b| score(a).total_cmp(&score(b))).unwrap()
+    }
+
+    fn root_visits(&self) -> impl Iterator<Item = (Action, u32)> + '_ {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|child| (self.nodes[*child].action.unwrap(), self.nodes[*child].visits))
+    }
+}
+
+impl Player for IsmctsAIPlayer {}
+
+impl ChooseTileStrategy for IsmctsAIPlayer {
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        let moves = legal_moves(hand, snake);
+        if moves.len() == 1 {
+            return moves[0];
+        }
+
+        // late in a game there may be nothing left to sample
+        let solution = self
+            .knowledge
+            .known(hand, snake)
+            .and_then(|position| self.solver.solve(&position));
+        if let Some(Solution {
+            best: Some(Action::Play(mv)),
+            ..
+        }) = solution
+        {
+            return mv;
+        }
+
+        let mut tree = InfoSetTree::new(self.knowledge.seat, self.exploration);
+        let mut clock = Clock::start(self.budget);
+        while clock.tick() {
+            let mut deal = self.knowledge.sample(hand, snake, &mut self.rng);
+            tree.iterate(&mut deal, &mut self.rng);
+        }
+
+        let (best, _) = tree.root_visits().max_by_key(|(_, visits)| *visits).unwrap();
+        match best {
+            Action::Play(mv) => mv,
+            _ => unreachable!("the player only chooses when a tile fits"),
+        }
+    }
+}
+
+impl GameObserver for IsmctsAIPlayer {
+    fn game_started(
+        &mut self,
+        seat: usize,
+        hand: &Hand,
+        hand_sizes: &[usize],
+        snake: &Snake,
+        game_mode: GameMode,
+        partner: Option<usize>,
+    ) {
+        self.knowledge.game_started(seat, hand, hand_sizes, snake, game_mode, partner);
+        self.solver.clear();
+    }
+
+    fn opponent_drew_tile(&mut self, seat: usize) {
+        self.knowledge.opponent_drew_tile(seat);
+    }
+
+    fn i_drew_tile(&mut self, tile: Tile) {
+        self.knowledge.i_drew_tile(tile);
+    }
+
+    fn opponent_played_tile(&mut self, seat: usize, tile: Tile) {
+        self.knowledge.opponent_played_tile(seat, tile);
+    }
+
+    fn opponent_was_blocked(&mut self, seat: usize, pips: [u8; 2]) {
+        self.knowledge.opponent_was_blocked(seat, pips);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{InfoSetTree, IsmctsAIPlayer};
+    use crate::{
+        choose_tile_strategy::ChooseTileStrategy,
+        game::GameMode,
+        game_move::{Action, Move, Side},
+        game_observer::GameObserver,
+        mcts::Budget,
+        position::fixtures::{go_out_first, going_out_first, hand, position},
+        tile::Tile,
+    };
+
+    #[test]
+    fn with_nothing_hidden_it_finds_the_way_out() {
+        let mut deal = go_out_first();
+
+        let mut tree = InfoSetTree::new(0, 0.7);
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..2000 {
+            tree.iterate(&mut deal, &mut rng);
+        }
+
+        let (best, _) = tree.root_visits().max_by_key(|(_, visits)| *visits).unwrap();
+        assert_eq!(best, Action::Play(going_out_first()));
+    }
+
+    #[test]
+    fn keeps_a_seat_about_to_go_out_from_following() {
+        let (hand, snake) = (hand(&[(6, 1), (4, 5)]), position(&[], &[(4, 6)]).snake);
+        let mut player = IsmctsAIPlayer::new(Budget::Iterations(500)).seed(1);
+        player.game_started(0, &hand, &[2, 1], &snake, GameMode::Block, None);
+        // seat 1 holds one last tile, neither a 4 nor a 1
+        player.opponent_was_blocked(1, [4, 1]);
+
+        // [4|5] would leave a 5 or a 6 for seat 1 to go out on, [6|1] goes
+        // out after seat 1 passes
+        assert_eq!(player.choose_move(&hand, &snake), Move::new(Tile::new(6, 1), Side::Right));
+    }
+}
@@ -0,0 +1,260 @@
+use arrayvec::ArrayVec;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    boneyard::Boneyard,
+    game::{GameMode, MAX_PLAYERS},
+    game_observer::GameObserver,
+    hand::Hand,
+    position::Position,
+    possible_hand::PossibleHand,
+    snake::Snake,
+    tile::Tile,
+};
+
+/// How often `Knowledge::sample` reshuffles before it gives up on what it
+/// ruled out.
+const SAMPLE_ATTEMPTS: usize = 32;
+
+/// What one seat can tell about the hidden tiles, built up from the
+/// `GameObserver` callbacks. Search players keep one and `sample` the tiles
+/// they can't see.
+#[derive(Debug, Clone)]
+pub struct Knowledge {
+    pub seat: usize,
+    pub partner: Option<usize>,
+    pub game_mode: GameMode,
+    /// indexed by seat, my own seat is left untouched
+    pub hands: ArrayVec<PossibleHand, MAX_PLAYERS>,
+}
+
+impl Default for Knowledge {
+    fn default() -> Self {
+        Knowledge {
+            seat: 0,
+            partner: None,
+            game_mode: GameMode::Block,
+            hands: ArrayVec::new(),
+        }
+    }
+}
+
+impl Knowledge {
+    /// Every tile of the set that is neither in `hand` nor on the snake.
+    pub fn unseen(hand: &Hand, snake: &Snake) -> ArrayVec<Tile, 28> {
+        let mut unseen = Boneyard::new().tiles;
+        unseen.retain(|tile| {
+            let seen = hand.tiles.iter().chain(snake.tiles.iter());
+            !seen.map(Tile::normalized).any(|seen| seen == *tile)
+        });
+        unseen
+    }
+
+    /// A full position for my turn that agrees with everything seen so far:
+    /// the unseen tiles are dealt to the other seats as far as they could
+    /// hold them, and the rest goes into the boneyard in random order.
+    pub fn sample<R: Rng + ?Sized>(&self, hand: &Hand, snake: &Snake, rng: &mut R) -> Position {
+        let unseen = Self::unseen(hand, snake);
+        let (hands, boneyard) = (0..SAMPLE_ATTEMPTS)
+            .find_map(|_| self.deal(&unseen, true, rng))
+            // what was ruled out doesn't add up, the sizes still have to
+            .unwrap_or_else(|| self.deal(&unseen, false, rng).unwrap());
+
+        let hands = hands
+            .into_iter()
+            .enumerate()
+            .map(|(seat, other)| if seat == self.seat { hand.clone() } else { other });
+        self.position(hands, boneyard, snake)
+    }
+
+    /// The position for my turn once nothing is hidden anymore: every other
+    /// seat could only hold exactly the tiles it does. In `GameMode::Draw`
+    /// the boneyard also has to be empty, its order is never known.
+    pub fn known(&self, hand: &Hand, snake: &Snake) -> Option<Position> {
+        let mut boneyard = Self::unseen(hand, snake);
+        let mut hands: ArrayVec<Hand, MAX_PLAYERS> = ArrayVec::new();
+        for (seat, possible) in self.hands.iter().enumerate() {
+            if seat == self.seat {
+                hands.push(hand.clone());
+                continue;
+            }
+            let candidates = boneyard.iter().copied().filter(|tile| possible.contains(*tile));
+            if candidates.clone().count() != possible.size as usize {
+                return None;
+            }
+            let mut theirs = Hand::default();
+            theirs.tiles.extend(candidates);
+            boneyard.retain(|tile| !theirs.tiles.contains(tile));
+            hands.push(theirs);
+        }
+        if self.game_mode == GameMode::Draw && !boneyard.is_empty() {
+            return None;
+        }
+
+        Some(self.position(hands, boneyard, snake))
+    }
+
+    /// My turn in a game played by the rules I was told about.
+    fn position(
+        &self,
+        hands: impl IntoIterator<Item = Hand>,
+        boneyard: ArrayVec<Tile, 28>,
+        snake: &Snake,
+    ) -> Position {
+        Position {
+            game_mode: self.game_mode,
+            partnership: self.partner.is_some(),
+            ..Position::new(hands, Boneyard { tiles: boneyard }, snake.clone(), self.seat)
+        }
+    }
+
+    /// Hands of the known sizes for the other seats, made of tiles each seat
+    /// could hold when `strict`, and the shuffled rest. My own hand is left
+    /// empty.
+    fn deal<R: Rng + ?Sized>(
+        &self,
+        unseen: &[Tile],
+        strict: bool,
+        rng: &mut R,
+    ) -> Option<(ArrayVec<Hand, MAX_PLAYERS>, ArrayVec<Tile, 28>)> {
+        let mut left: ArrayVec<Tile, 28> = unseen.iter().copied().collect();
+        left.shuffle(rng);
+
+        // the seats with the fewest candidates pick first
+        let mut seats: ArrayVec<usize, MAX_PLAYERS> =
+            (0..self.hands.len()).filter(|seat| *seat != self.seat).collect();
+        seats.sort_by_key(|seat| self.hands[*seat].possible_tiles.len());
+
+        let mut hands: ArrayVec<Hand, MAX_PLAYERS> = self.hands.iter().map(|_| Hand::default()).collect();
+        for seat in seats {
+            let possible = &self.hands[seat];
+            let mut index = 0;
+            while hands[seat].tiles.len() < possible.size as usize && index < left.len() {
+                if !strict || possible.contains(left[index]) {
+                    hands[seat].tiles.push(left.remove(index));
+                } else {
+                    index += 1;
+                }
+            }
+            if strict && hands[seat].tiles.len() < possible.size as usize {
+                return None;
+            }
+        }
+        Some((hands, left))
+    }
+}
+
+impl GameObserver for Knowledge {
+    fn game_started(
+        &mut self,
+        seat: usize,
+        hand: &Hand,
+        hand_sizes: &[usize],
+        snake: &Snake,
+        game_mode: GameMode,
+        partner: Option<usize>,
+    ) {
+        self.seat = seat;
+        self.partner = partner;
+        self.game_mode = game_mode;
+        self.hands.clear();
+        for hand_size in hand_sizes {
+            let mut possible = PossibleHand::new();
+            for tile in hand.tiles.iter().chain(snake.tiles.iter()) {
+                possible.remove_tile(*tile);
+            }
+            possible.size = *hand_size as u8;
+            self.hands.push(possible);
+        }
+    }
+
+    fn opponent_drew_tile(&mut self, seat: usize) {
+        self.hands[seat].size += 1;
+    }
+
+    fn i_drew_tile(&mut self, tile: Tile) {
+        for possible in self.hands.iter_mut() {
+            possible.remove_tile(tile);
+        }
+    }
+
+    fn opponent_played_tile(&mut self, seat: usize, tile: Tile) {
+        for possible in self.hands.iter_mut() {
+            possible.remove_tile(tile);
+        }
+        self.hands[seat].size -= 1;
+    }
+
+    fn opponent_was_blocked(&mut self, seat: usize, pips: [u8; 2]) {
+        self.hands[seat].remove_tiles_with_pips(pips);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::Knowledge;
+    use crate::{
+        game::GameMode,
+        game_move::Side,
+        game_observer::GameObserver,
+        position::fixtures::hand,
+        snake::Snake,
+        tile::Tile,
+    };
+
+    #[test]
+    fn a_sample_deals_every_unseen_tile_once() {
+        let mut knowledge = Knowledge::default();
+        let mine = hand(&[(0, 0), (1, 2), (6, 5)]);
+        let mut snake = Snake::new();
+        snake.play(Tile::new(3, 5), Side::Right).unwrap();
+        knowledge.game_started(1, &mine, &[4, 3, 5], &snake, GameMode::Draw, None);
+
+        let position = knowledge.sample(&mine, &snake, &mut StdRng::seed_from_u64(7));
+
+        assert_eq!(position.validate(), Ok(()));
+        assert_eq!(position.current_player, 1);
+        assert_eq!(position.hands[1], mine);
+        assert_eq!(position.hands[0].tiles.len(), 4);
+        assert_eq!(position.hands[2].tiles.len(), 5);
+        assert_eq!(position.boneyard.tiles.len(), 28 - 4 - 3 - 5 - 1);
+    }
+
+    #[test]
+    fn the_position_is_known_once_the_candidates_run_out() {
+        let mut knowledge = Knowledge::default();
+        let mine = hand(&[(0, 0)]);
+        let mut snake = Snake::new();
+        snake.play(Tile::new(3, 5), Side::Right).unwrap();
+        knowledge.game_started(0, &mine, &[1, 2], &snake, GameMode::Block, None);
+        assert!(knowledge.known(&mine, &snake).is_none());
+
+        // only [0|6] and [6|6] are left without any of these pips
+        for pips in [[3, 5], [1, 2], [4, 4]] {
+            knowledge.opponent_was_blocked(1, pips);
+        }
+        let position = knowledge.known(&mine, &snake).unwrap();
+        assert_eq!(position.hands[1].tiles.as_slice(), &[Tile::new(0, 6), Tile::new(6, 6)]);
+        assert_eq!(position.validate(), Ok(()));
+    }
+
+    #[test]
+    fn a_sample_respects_what_was_ruled_out() {
+        let mut knowledge = Knowledge::default();
+        let mine = hand(&[(0, 0)]);
+        let mut snake = Snake::new();
+        snake.play(Tile::new(3, 5), Side::Right).unwrap();
+        knowledge.game_started(0, &mine, &[1, 7], &snake, GameMode::Block, None);
+        knowledge.opponent_was_blocked(1, [3, 5]);
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let position = knowledge.sample(&mine, &snake, &mut rng);
+            for tile in position.hands[1].tiles.iter() {
+                assert!(![3, 5].contains(&tile.left) && ![3, 5].contains(&tile.right));
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+pub mod analysis;
+pub mod boneyard;
+pub mod cfr;
+pub mod cfr_ai_player;
+pub mod choose_tile_strategy;
+pub mod expectimax_ai_player;
+pub mod first_possible_tile_ai_player;
+pub mod game;
+pub mod game_move;
+pub mod game_observer;
+pub mod greedy_ai_player;
+pub mod hand;
+pub mod human_player;
+pub mod ismcts_ai_player;
+pub mod knowledge;
+pub mod mcts;
+pub mod mcts_ai_player;
+pub mod network;
+pub mod neural_ai_player;
+pub mod observent_ai_player;
+pub mod player;
+pub mod position;
+pub mod possible_hand;
+pub mod record;
+pub mod simulation;
+pub mod snake;
+pub mod solver;
+pub mod statistics;
+pub mod tile;
+pub mod tournament;
+pub mod training;
+pub mod tui_player;
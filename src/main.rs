@@ -1,31 +1,27 @@
-use std::sync::{
-    atomic::{AtomicI32, AtomicI64},
-    Arc,
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::Arc,
 };
 
-use first_possible_tile_ai_player::FirstPossibleTileAIPlayer;
-use game::{GameState, Winner};
-use greedy_ai_player::GreedyAIPlayer;
-use human_player::HumanPlayer;
-use observent_ai_player::ObserventAIPlayer;
-use quanta::Clock;
-use std::thread::available_parallelism;
-
-use crate::game::Game;
-
-mod boneyard;
-mod choose_tile_strategy;
-mod first_possible_tile_ai_player;
-mod game;
-mod game_observer;
-mod greedy_ai_player;
-mod hand;
-mod human_player;
-mod player;
-mod snake;
-mod tile;
-mod observent_ai_player;
-mod possible_hand;
+use rust_domino::{
+    analysis::analyze,
+    game::{Game, GameMode, GameState, Winner},
+    game_move::Action,
+    human_player::HumanPlayer,
+    network::Network,
+    neural_ai_player::NeuralAIPlayer,
+    player::Player,
+    record::{Event, GameRecord},
+    simulation::Simulation,
+    statistics::Sprt,
+    tile::Tile,
+    tournament::{find, registered, Entrant, Tournament},
+    tui_player::TuiPlayer,
+};
 
 // use std::alloc::{System, GlobalAlloc, Layout};
 // use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
@@ -52,126 +48,424 @@ mod possible_hand;
 // #[global_allocator]
 // static A: Counter = Counter;
 
-fn stress_test() {
-    // create an atomic counter
-    let mut counter = Arc::new(AtomicI64::new(0));
-    let mut winner_0 = Arc::new(AtomicI64::new(0));
-    let mut winner_1 = Arc::new(AtomicI64::new(0));
-    // let mut _score_0 = Arc::new(AtomicI32::new(0));
-    // let mut _score_1 = Arc::new(AtomicI32::new(0));
-    // let default_parallelism_approx = available_parallelism().unwrap().get();
-    let default_parallelism_approx = 1;
-
-    let mut threads = Vec::new();
-    for _ in 0..default_parallelism_approx {
-        let counter = counter.clone();
-        let winner_0 = winner_0.clone();
-        let winner_1 = winner_1.clone();
-        // let _score_0 = _score_0.clone();
-        // let _score_1 = _score_1.clone();
-
-        threads.push(std::thread::spawn(move || {
-            let mut should_swap = false;
-            let mut thread_rng = rand::thread_rng();
-            // for _ in 0..1000000 {
-            loop {
-                // meausre clock cycles
-                let mut game =
-                    Game::<GreedyAIPlayer, FirstPossibleTileAIPlayer>::new(&mut thread_rng)
-                        .swap_players(should_swap);
-                loop {
-                    let (new_game, state) = game.play();
-                    game = new_game;
-                    if let GameState::Finished {
-                        winner,
-                        score_0,
-                        score_1,
-                    } = state
-                    {
-                        if let Some(winner) = winner {
-                            match winner {
-                                Winner::Player0 => {
-                                    winner_0.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                                }
-                                Winner::Player1 => {
-                                    winner_1.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
-                                }
-                            };
-                            // _score_0.fetch_add(score_0, std::sync::atomic::Ordering::Relaxed);
-                            // _score_1.fetch_add(score_1, std::sync::atomic::Ordering::Relaxed);
-                        } else {
-                        }
-
-                        break;
+const USAGE: &str = "usage: rust_domino [COMMAND] [OPTIONS]
+
+commands:
+  play        play against an AI full screen, the default
+              [--opponent PLAYER] [--games N] [--save FILE] [--plain]
+              with more than one game, --save numbers a file for each
+  simulate    play two AIs against each other
+              [--first PLAYER] [--second PLAYER] [--games N] [--threads N]
+              [--duplicate] [--sprt ELO0 ELO1]
+  tournament  play every pair of AIs and rate them
+              [--players PLAYER,PLAYER,...] [--games N] [--threads N] [--duplicate]
+  replay      show a saved game turn by turn
+              FILE
+  analyze     compare every move of a saved game with the best one
+              FILE
+
+every command but replay and analyze also takes [--mode block|draw] [--seed N]
+
+players: first-possible, greedy, observent, expectimax, mcts, ismcts, or
+neural:WEIGHTS for a network trained with the train binary";
+
+/// Bad usage prints the usage and exits with 2, anything else that goes
+/// wrong exits with 1.
+enum Error {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Usage(message)
+    }
+}
+
+fn parse<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("`{}` is not a valid value for {}", value, flag))
+}
+
+/// A registered player, or `neural:<weights>`.
+fn player(name: &str) -> Result<Entrant, Error> {
+    if let Some(path) = name.strip_prefix("neural:") {
+        let network = Network::load(path).map_err(|error| Error::Failed(format!("can't load {}: {}", path, error)))?;
+        return Ok(Entrant::new(name, Arc::new(move |_| Box::new(NeuralAIPlayer::new(network.clone())))));
+    }
+    find(name).ok_or_else(|| Error::Usage(format!("unknown player `{}`", name)))
+}
+
+/// The options every command shares.
+struct Common {
+    game_mode: GameMode,
+    seed: u64,
+}
+
+impl Common {
+    fn new() -> Common {
+        Common {
+            game_mode: GameMode::Block,
+            seed: rand::random(),
+        }
+    }
+
+    /// Takes `flag` if it is one of the shared options.
+    fn parse(&mut self, flag: &str, args: &mut impl Iterator<Item = String>) -> Result<bool, String> {
+        match flag {
+            "--mode" => {
+                self.game_mode = match args.next().as_deref() {
+                    Some("block") => GameMode::Block,
+                    Some("draw") => GameMode::Draw,
+                    _ => return Err("--mode is either block or draw".to_string()),
+                }
+            }
+            "--seed" => self.seed = parse(flag, args.next())?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+fn unknown(flag: &str) -> String {
+    format!("unknown option `{}`", flag)
+}
+
+fn play(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut common = Common::new();
+    let mut opponent = find("observent").unwrap();
+    let mut games = None;
+    let mut save = None;
+    // line by line, also when there is no terminal to take over
+    let mut plain = !io::stdin().is_terminal() || !io::stdout().is_terminal();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--plain" => plain = true,
+            "--opponent" => opponent = player(&parse::<String>(&flag, args.next())?)?,
+            "--games" => games = Some(parse::<u64>(&flag, args.next())?),
+            "--save" => save = Some(parse::<PathBuf>(&flag, args.next())?),
+            _ if common.parse(&flag, &mut args)? => {}
+            _ => return Err(Error::Usage(unknown(&flag))),
+        }
+    }
+
+    for round in 0..games.unwrap_or(u64::MAX) {
+        let seed = common.seed.wrapping_add(round);
+        let human: Box<dyn Player> = if plain { Box::new(HumanPlayer) } else { Box::new(TuiPlayer::default()) };
+        let mut game = Game::from_seed(seed, vec![human, (opponent.factory)(seed)]).game_mode(common.game_mode);
+        // the actual game loop
+        loop {
+            if plain {
+                show(&game);
+            }
+            let (new_game, state) = game.play();
+            game = new_game;
+            if game.abandoned() {
+                println!("You quit the game");
+                break;
+            }
+            if let GameState::Finished { winner, scores } = state {
+                match winner {
+                    Some(Winner::Seat(seat)) => {
+                        println!("Player {} won with score {}", seat, scores[seat])
+                    }
+                    // team t is made up of seats t and t + 2
+                    Some(Winner::Team(team)) => {
+                        println!("Team {} won with score {}", team, scores[team])
                     }
+                    None => println!("Draw with score {} - {}", scores[0], scores[1]),
                 }
+                println!("{}", game.record());
+                break;
+            }
+        }
 
-                should_swap = !should_swap;
-                counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if let Some(path) = &save {
+            let path = save_path(path, round, games);
+            fs::write(&path, game.record().to_string())
+                .map_err(|error| Error::Failed(format!("can't save to {}: {}", path.display(), error)))?;
+            println!("saved {}", path.display());
+        }
+        if game.abandoned() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// What seat 0 gets to see: the snake, their own hand and how many tiles
+/// everyone else holds.
+fn show(game: &Game) {
+    let glyphs = |tiles: &[Tile]| -> String {
+        tiles.iter().map(|tile| format!(" {}", tile.vertical_glyph())).collect()
+    };
+    let snake: String = game.snake().tiles.iter().map(|tile| format!(" {}", tile.horizontal_glyph())).collect();
+    println!("Snake {}", snake);
+    println!("Your hand: {}", glyphs(&game.hand(0).tiles));
+    for seat in 1..game.player_count() {
+        println!("Player {} holds {} tiles", seat, game.hand(seat).tiles.len());
+    }
+    println!("{} tiles left to draw", game.boneyard().tiles.len());
+}
+
+/// `--save` as given for a single game, numbered from 1 for each round of
+/// more, e.g. `game-2.txt` for the second.
+fn save_path(path: &Path, round: u64, games: Option<u64>) -> PathBuf {
+    if games == Some(1) {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut name = format!("{}-{}", stem, round + 1);
+    if let Some(extension) = path.extension() {
+        name = format!("{}.{}", name, extension.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+fn simulate(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut common = Common::new();
+    let mut first = find("greedy").unwrap();
+    let mut second = find("first-possible").unwrap();
+    let mut games = 10_000;
+    let mut threads = None;
+    let mut duplicate = false;
+    let mut sprt = None;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--first" => first = player(&parse::<String>(&flag, args.next())?)?,
+            "--second" => second = player(&parse::<String>(&flag, args.next())?)?,
+            "--games" => games = parse(&flag, args.next())?,
+            "--threads" => threads = Some(parse(&flag, args.next())?),
+            "--duplicate" => duplicate = true,
+            "--sprt" => {
+                let (elo0, elo1) = (parse(&flag, args.next())?, parse(&flag, args.next())?);
+                if elo0 >= elo1 {
+                    return Err(Error::Usage("--sprt needs ELO0 below ELO1".to_string()));
+                }
+                sprt = Some(Sprt::new(elo0, elo1));
             }
-        }));
+            _ if common.parse(&flag, &mut args)? => {}
+            _ => return Err(Error::Usage(unknown(&flag))),
+        }
+    }
+    if threads == Some(0) {
+        return Err(Error::Usage("--threads must be at least 1".to_string()));
     }
 
-    let now = std::time::Instant::now();
+    let mut simulation = Simulation::new(first.factory, second.factory)
+        .games(games)
+        .seed(common.seed)
+        .game_mode(common.game_mode)
+        .duplicate(duplicate);
+    if let Some(threads) = threads {
+        simulation = simulation.threads(threads);
+    }
+    if let Some(sprt) = sprt {
+        simulation = simulation.sprt(sprt);
+    }
 
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(5));
-        let count = counter.load(std::sync::atomic::Ordering::Relaxed);
-        println!("{} games played", count);
+    println!("{} against {}, seed {}", first.name, second.name, common.seed);
+    println!("{}", simulation.run());
+    Ok(())
+}
+
+fn tournament(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut common = Common::new();
+    let mut entrants = registered();
+    let mut games = 200;
+    let mut threads = None;
+    let mut duplicate = false;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--players" => {
+                entrants = parse::<String>(&flag, args.next())?
+                    .split(',')
+                    .map(player)
+                    .collect::<Result<_, Error>>()?
+            }
+            "--games" => games = parse(&flag, args.next())?,
+            "--threads" => threads = Some(parse(&flag, args.next())?),
+            "--duplicate" => duplicate = true,
+            _ if common.parse(&flag, &mut args)? => {}
+            _ => return Err(Error::Usage(unknown(&flag))),
+        }
+    }
+    if entrants.len() < 2 {
+        return Err(Error::Usage("--players needs at least two players".to_string()));
+    }
+    if threads == Some(0) {
+        return Err(Error::Usage("--threads must be at least 1".to_string()));
+    }
+
+    println!("seed {}", common.seed);
+    let mut tournament = Tournament::new(entrants.clone())
+        .games(games)
+        .seed(common.seed)
+        .game_mode(common.game_mode)
+        .duplicate(duplicate);
+    if let Some(threads) = threads {
+        tournament = tournament.threads(threads);
+    }
+    let standings = tournament.run_with(|first, second, report| {
         println!(
-            "{} games per second",
-            count as u64 / now.elapsed().as_secs()
+            "{} against {}: {} won, {} drawn, {} lost",
+            entrants[first].name, entrants[second].name, report.wins, report.draws, report.losses
         );
-        let w0 = winner_0.load(std::sync::atomic::Ordering::Relaxed);
-        let w1 = winner_1.load(std::sync::atomic::Ordering::Relaxed);
-        // let s0 = _score_0.load(std::sync::atomic::Ordering::Relaxed);
-        // let s1 = _score_1.load(std::sync::atomic::Ordering::Relaxed);
-        println!("{} games won by player 0", w0);
-        println!("{} games won by player 1", w1);
-        // println!(
-        //     "{} average score for player 0",
-        //     s0.checked_div(w0).unwrap_or_default()
-        // );
-        // println!(
-        //     "{} average score for player 1",
-        //     s1.checked_div(w1).unwrap_or_default()
-        // );
-        // println!("allocated bytes before main: {}", ALLOCATED.load(Relaxed));
-    }
-
-    for thread in threads {
-        thread.join().unwrap();
-    }
-}
-
-fn singleplayer() {
-    let mut rng = rand::thread_rng();
-    // make the game infinite
+    });
+    println!("\n{}", standings);
+    Ok(())
+}
+
+/// The saved game named by the only argument.
+fn record(mut args: impl Iterator<Item = String>) -> Result<GameRecord, Error> {
+    let path = args.next().ok_or_else(|| "which game? a saved game file is needed".to_string())?;
+    if let Some(flag) = args.next() {
+        return Err(Error::Usage(unknown(&flag)));
+    }
+    let text = fs::read_to_string(&path).map_err(|error| Error::Failed(format!("can't read {}: {}", path, error)))?;
+    text.parse().map_err(|error| Error::Failed(format!("{}: {}", path, error)))
+}
+
+fn replay(args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let record = record(args)?;
+    let mut game = record.replay().map_err(|error| Error::Failed(error.to_string()))?;
+    let mut shown = 0;
+    println!("{:#?}", game);
     loop {
-        let mut game = Game::<HumanPlayer, ObserventAIPlayer>::new(&mut rng);
-        // the actual game loop
-        loop {
+        let (new_game, state) = game.play();
+        game = new_game;
+        for event in game.record().events[shown..].iter() {
+            match event {
+                Event::Draw { seat, tile } => println!("seat {} draws {:?}", seat, tile),
+                Event::Play { seat, mv } => println!("seat {} plays {:?}", seat, mv),
+                Event::Pass { seat } => println!("seat {} passes", seat),
+            }
+        }
+        if game.record().events.len() > shown {
             println!("{:#?}", game);
-            let (new_game, state) = game.play();
-            game = new_game;
-            if let GameState::Finished {
-                winner,
-                score_0,
-                score_1,
-            } = state
-            {
-                if let Some(winner) = winner {
-                    println!("Player {:?} won with score {}", winner, score_0);
-                } else {
-                    println!("Draw with score {} - {}", score_0, score_1);
+        }
+        shown = game.record().events.len();
+        if game.abandoned() {
+            println!("the record stops here");
+            return Ok(());
+        }
+        if let GameState::Finished { winner, scores } = state {
+            match winner {
+                Some(Winner::Seat(seat)) => println!("seat {} won with score {}", seat, scores[seat]),
+                Some(Winner::Team(team)) => println!("team {} won with score {}", team, scores[team]),
+                None => println!("draw with score {} - {}", scores[0], scores[1]),
+            }
+            return Ok(());
+        }
+    }
+}
+
+fn analyze_game(args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let record = record(args)?;
+    let annotations = analyze(&record).map_err(|error| Error::Failed(error.to_string()))?;
+    let mut lost = [0; 2];
+    for annotation in annotations.iter() {
+        let Event::Play { seat, mv } = annotation.event else {
+            continue;
+        };
+        print!("{:>3}  seat {} plays {:<12}", annotation.index + 1, seat, format!("{:?}", mv));
+        match (annotation.best, annotation.played) {
+            (_, _) if annotation.choices == 1 => println!("forced"),
+            (Some(best), Some(played)) if annotation.lost() > 0 => {
+                if let Some(seat_lost) = lost.get_mut(seat) {
+                    *seat_lost += annotation.lost();
                 }
-                break;
+                let better = match best.best {
+                    Some(Action::Play(mv)) => format!("{:?}", mv),
+                    _ => "another move".to_string(),
+                };
+                println!("{:+4}, {} wins {:+}", played, better, best.value)
             }
+            (Some(best), Some(_)) => println!("{:+4}, best", best.value),
+            _ => println!("can't be solved"),
         }
     }
+    if record.start.hands.len() == 2 {
+        println!("points given away: seat 0 {}, seat 1 {}", lost[0], lost[1]);
+    }
+    Ok(())
 }
 
 fn main() {
-    singleplayer();
-    // stress_test();
+    let mut args = std::env::args().skip(1).peekable();
+    // plain options without a command are for play
+    let command = match args.peek().map(String::as_str) {
+        Some("--help") => "help".to_string(),
+        Some(arg) if !arg.starts_with("--") => args.next().unwrap(),
+        _ => "play".to_string(),
+    };
+    let result = match command.as_str() {
+        "play" => play(args),
+        "simulate" => simulate(args),
+        "tournament" => tournament(args),
+        "replay" => replay(args),
+        "analyze" => analyze_game(args),
+        "help" => {
+            println!("{}", USAGE);
+            return;
+        }
+        _ => Err(Error::Usage(format!("unknown command `{}`", command))),
+    };
+    match result {
+        Ok(()) => {}
+        Err(Error::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            process::exit(2);
+        }
+        Err(Error::Failed(message)) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::Path, process};
+
+    use super::{replay, save_path, Error};
+
+    #[test]
+    fn saving_several_games_numbers_the_files() {
+        let path = Path::new("games/match.txt");
+        assert_eq!(save_path(path, 0, Some(1)), path);
+        assert_eq!(save_path(path, 0, Some(3)), Path::new("games/match-1.txt"));
+        assert_eq!(save_path(path, 2, None), Path::new("games/match-3.txt"));
+        assert_eq!(save_path(Path::new("match"), 1, Some(2)), Path::new("match-2"));
+    }
+
+    #[test]
+    fn replaying_a_bad_record_fails_without_panicking() {
+        let start = "mode block\nhand 0 [0|1] [1|2]\nhand 1 [2|2] [4|5]\nboneyard\nsnake\nturn 0\n";
+        let records = [
+            // seat 0 doesn't hold [3|3]
+            format!("{}play 0 [3|3] right\n", start),
+            format!("{}play 1 [2|2] right\n", start),
+            // seat 0 still holds [1|2] and can't have won
+            format!("{}play 0 [0|1] right\nresult seat 0 scores 11 0\n", start),
+        ];
+        for (index, record) in records.iter().enumerate() {
+            let path = env::temp_dir().join(format!("rust_domino_bad_record_{}_{}", process::id(), index));
+            fs::write(&path, record).unwrap();
+            let result = replay([path.display().to_string()].into_iter());
+            fs::remove_file(&path).unwrap();
+            assert!(matches!(result, Err(Error::Failed(_))), "record {}", index);
+        }
+    }
+
+    #[test]
+    fn replaying_a_quit_game_stops_where_it_was_left() {
+        // what `play --save` writes when the human quits on their second turn
+        let record = "mode block\nhand 0 [0|1] [1|2]\nhand 1 [2|2] [4|5]\nboneyard\nsnake\nturn 0\nplay 0 [0|1] right\npass 1\n";
+        let path = env::temp_dir().join(format!("rust_domino_quit_record_{}", process::id()));
+        fs::write(&path, record).unwrap();
+        let result = replay([path.display().to_string()].into_iter());
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_ok());
+    }
 }
@@ -0,0 +1,226 @@
+use std::time::Duration;
+
+use arrayvec::ArrayVec;
+use rand::Rng;
+
+use crate::{
+    game::GameState,
+    game_move::{Action, MAX_MOVES},
+    position::Position,
+};
+
+/// How long a search may run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    Iterations(u32),
+    Time(Duration),
+}
+
+impl Budget {
+    /// One of `parts` equal shares, never less than a single iteration.
+    pub fn split(self, parts: u32) -> Budget {
+        match self {
+            Budget::Iterations(iterations) => Budget::Iterations((iterations / parts).max(1)),
+            Budget::Time(time) => Budget::Time(time / parts),
+        }
+    }
+}
+
+/// Counts the iterations run against a `Budget`.
+pub struct Clock {
+    budget: Budget,
+    started: quanta::Instant,
+    iterations: u32,
+}
+
+impl Clock {
+    pub fn start(budget: Budget) -> Clock {
+        Clock {
+            budget,
+            started: quanta::Instant::now(),
+            iterations: 0,
+        }
+    }
+
+    /// Whether another iteration fits in the budget. The first always does.
+    pub fn tick(&mut self) -> bool {
+        let more = self.iterations == 0
+            || match self.budget {
+                Budget::Iterations(iterations) => self.iterations < iterations,
+                Budget::Time(time) => self.started.elapsed() < time,
+            };
+        self.iterations += 1;
+        more
+    }
+}
+
+/// What a finished game is worth to `seat`: 1 for a win, 0 for a loss and
+/// half for a blocked game nobody won.
+pub fn reward(state: &GameState, seat: usize) -> f64 {
+    match state {
+        GameState::Finished { winner: Some(winner), .. } if winner.contains(seat) => 1.0,
+        GameState::Finished { winner: Some(_), .. } => 0.0,
+        _ => 0.5,
+    }
+}
+
+/// Plays random actions until the game is over.
+pub fn playout<R: Rng + ?Sized>(position: &mut Position, rng: &mut R) -> GameState {
+    loop {
+        if let state @ GameState::Finished { .. } = position.state() {
+            return state;
+        }
+        let actions = position.legal_actions();
+        position.apply(actions[rng.gen_range(0..actions.len())]);
+    }
+}
+
+struct Node {
+    /// `None` for the root
+    action: Option<Action>,
+    /// the seat that took `action`, whose reward the node keeps
+    actor: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: ArrayVec<Action, MAX_MOVES>,
+    visits: u32,
+    reward: f64,
+}
+
+/// A UCT search tree over one fully known position.
+pub struct Tree {
+    nodes: Vec<Node>,
+    exploration: f64,
+}
+
+impl Tree {
+    pub fn new(root: &Position, exploration: f64) -> Tree {
+        let root = Node {
+            action: None,
+            actor: root.current_player,
+            parent: None,
+            children: Vec::new(),
+            untried: Self::actions(root),
+            visits: 0,
+            reward: 0.0,
+        };
+        Tree {
+            nodes: vec![root],
+            exploration,
+        }
+    }
+
+    fn actions(position: &Position) -> ArrayVec<Action, MAX_MOVES> {
+        match position.state() {
+            GameState::Playing => position.legal_actions(),
+            GameState::Finished { .. } => ArrayVec::new(),
+        }
+    }
+
+    /// Selects down the tree, expands one action, plays the game out and
+    /// backs the result up. `position` must be the root and is left as it was.
+    pub fn iterate<R: Rng + ?Sized>(&mut self, position: &mut Position, rng: &mut R) {
+        let start = position.history.len();
+
+        let mut node = 0;
+        while self.nodes[node].untried.is_empty() && !self.nodes[node].children.is_empty() {
+            node = self.select(node);
+            position.apply(self.nodes[node].action.unwrap());
+        }
+
+        if !self.nodes[node].untried.is_empty() {
+            let untried = &mut self.nodes[node].untried;
+            let action = untried.swap_remove(rng.gen_range(0..untried.len()));
+            let actor = position.current_player;
+            position.apply(action);
+
+            let child = self.nodes.len();
+            self.nodes.push(Node {
+                action: Some(action),
+                actor,
+                parent: Some(node),
+                children: Vec::new(),
+                untried: Self::actions(position),
+                visits: 0,
+                reward: 0.0,
+            });
+            self.nodes[node].children.push(child);
+            node = child;
+        }
+
+        let state = playout(position, rng);
+
+        let mut next = Some(node);
+        while let Some(node) = next {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            node.reward += reward(&state, node.actor);
+            next = node.parent;
+        }
+
+        while position.history.len() > start {
+            position.undo();
+        }
+    }
+
+    /// The child with the best UCB1 score.
+    fn select(&self, node: usize) -> usize {
+        let log_visits = (self.nodes[node].visits as f64).ln();
+        let score = |child: &usize| {
+            let child = &self.nodes[*child];
+            child.reward / child.visits as f64 + self.exploration * (log_visits / child.visits as f64).sqrt()
+        };
+        *self.nodes[node]
+            .children
+            .iter()
+            .max_by(|a, b| score(a).total_cmp(&score(b)))
+            .unwrap()
+    }
+
+    /// Every action tried from the root and how often it was visited.
+    pub fn root_visits(&self) -> impl Iterator<Item = (Action, u32)> + '_ {
+        self.nodes[0]
+            .children
+            .iter()
+            .map(|child| (self.nodes[*child].action.unwrap(), self.nodes[*child].visits))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{Budget, Clock, Tree};
+    use crate::{
+        game_move::Action,
+        position::fixtures::{go_out_first, going_out_first},
+    };
+
+    #[test]
+    fn a_clock_counts_down_its_iterations() {
+        let mut clock = Clock::start(Budget::Iterations(10).split(4));
+        assert_eq!((0..10).filter(|_| clock.tick()).count(), 2);
+
+        let mut clock = Clock::start(Budget::Time(Duration::ZERO));
+        assert!(clock.tick());
+        assert!(!clock.tick());
+    }
+
+    #[test]
+    fn finds_the_only_way_to_go_out_first() {
+        let mut position = go_out_first();
+        let start = position.clone();
+
+        let mut tree = Tree::new(&position, 1.4);
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..2000 {
+            tree.iterate(&mut position, &mut rng);
+        }
+
+        assert_eq!(position, start);
+        let (best, _) = tree.root_visits().max_by_key(|(_, visits)| *visits).unwrap();
+        assert_eq!(best, Action::Play(going_out_first()));
+    }
+}
@@ -0,0 +1,166 @@
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    choose_tile_strategy::ChooseTileStrategy,
+    game::GameMode,
+    game_move::{legal_moves, Action, Move},
+    game_observer::GameObserver,
+    hand::Hand,
+    knowledge::Knowledge,
+    mcts::{Budget, Clock, Tree},
+    player::Player,
+    snake::Snake,
+    solver::{Solution, Solver},
+    tile::Tile,
+};
+
+/// Plans with Monte Carlo tree search. Every search deals the hidden tiles a
+/// few times over from what the player has seen, searches each deal as if
+/// every hand were open, and plays the move visited most across all of them.
+pub struct MctsAIPlayer {
+    budget: Budget,
+    determinizations: u32,
+    exploration: f64,
+    rng: StdRng,
+    knowledge: Knowledge,
+    /// takes over once every tile is known
+    solver: Solver,
+}
+
+impl Default for MctsAIPlayer {
+    fn default() -> Self {
+        Self::new(Budget::Iterations(1000))
+    }
+}
+
+impl MctsAIPlayer {
+    /// `budget` is for a whole move, shared by every deal.
+    pub fn new(budget: Budget) -> Self {
+        MctsAIPlayer {
+            budget,
+            determinizations: 10,
+            exploration: std::f64::consts::SQRT_2,
+            rng: StdRng::from_entropy(),
+            knowledge: Knowledge::default(),
+            solver: Solver::new(),
+        }
+    }
+
+    /// How many deals of the hidden tiles to search per move.
+    pub fn determinizations(mut self, determinizations: u32) -> Self {
+        assert!(determinizations > 0, "at least one deal is needed");
+        self.determinizations = determinizations;
+        self
+    }
+
+    /// The UCB1 constant, higher tries unpromising moves more often.
+    pub fn exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    /// Makes the searches repeatable.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Player for MctsAIPlayer {}
+
+impl ChooseTileStrategy for MctsAIPlayer {
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        let moves = legal_moves(hand, snake);
+        if moves.len() == 1 {
+            return moves[0];
+        }
+
+        // late in a game there may be nothing left to sample
+        let solution = self
+            .knowledge
+            .known(hand, snake)
+            .and_then(|position| self.solver.solve(&position));
+        if let Some(Solution {
+            best: Some(Action::Play(mv)),
+            ..
+        }) = solution
+        {
+            return mv;
+        }
+
+        let mut visits = vec![0; moves.len()];
+        let budget = self.budget.split(self.determinizations);
+        for _ in 0..self.determinizations {
+            let mut position = self.knowledge.sample(hand, snake, &mut self.rng);
+            let mut tree = Tree::new(&position, self.exploration);
+            let mut clock = Clock::start(budget);
+            while clock.tick() {
+                tree.iterate(&mut position, &mut self.rng);
+            }
+
+            for (action, count) in tree.root_visits() {
+                if let Some(index) = moves.iter().position(|mv| action == Action::Play(*mv)) {
+                    visits[index] += count;
+                }
+            }
+        }
+
+        let best = (0..moves.len()).max_by_key(|index| visits[*index]).unwrap();
+        moves[best]
+    }
+}
+
+impl GameObserver for MctsAIPlayer {
+    fn game_started(
+        &mut self,
+        seat: usize,
+        hand: &Hand,
+        hand_sizes: &[usize],
+        snake: &Snake,
+        game_mode: GameMode,
+        partner: Option<usize>,
+    ) {
+        self.knowledge.game_started(seat, hand, hand_sizes, snake, game_mode, partner);
+        self.solver.clear();
+    }
+
+    fn opponent_drew_tile(&mut self, seat: usize) {
+        self.knowledge.opponent_drew_tile(seat);
+    }
+
+    fn i_drew_tile(&mut self, tile: Tile) {
+        self.knowledge.i_drew_tile(tile);
+    }
+
+    fn opponent_played_tile(&mut self, seat: usize, tile: Tile) {
+        self.knowledge.opponent_played_tile(seat, tile);
+    }
+
+    fn opponent_was_blocked(&mut self, seat: usize, pips: [u8; 2]) {
+        self.knowledge.opponent_was_blocked(seat, pips);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MctsAIPlayer;
+    use crate::{
+        choose_tile_strategy::ChooseTileStrategy,
+        game::GameMode,
+        game_observer::GameObserver,
+        mcts::Budget,
+        position::fixtures::{go_out_first, going_out_first},
+    };
+
+    #[test]
+    fn goes_out_first_when_the_opponent_cant_follow() {
+        let position = go_out_first();
+        let (hand, snake) = (&position.hands[0], &position.snake);
+        let mut player = MctsAIPlayer::new(Budget::Iterations(400)).determinizations(4).seed(1);
+        player.game_started(0, hand, &[3, 2], snake, GameMode::Block, None);
+        // whatever seat 1 holds, it has no 3 and no 2 to follow [5|2]
+        player.opponent_was_blocked(1, [3, 2]);
+
+        assert_eq!(player.choose_move(hand, snake), going_out_first());
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+};
+
+use arrayvec::ArrayVec;
+use dfdx::{
+    losses::mse_loss,
+    nn::{
+        modules::{Linear, ReLU},
+        BuildModule, Module, ZeroGrads,
+    },
+    optim::{Adam, AdamConfig, Optimizer as _},
+    shapes::{Axis, Const, HasShape, Rank1, Shape},
+    tensor::{AsArray, Cpu, Tensor, TensorFrom, TensorFromVec, Trace, WithEmptyTape},
+    tensor_ops::{Backward, MeanTo, SelectTo},
+};
+
+use crate::{
+    game::{Game, MAX_PLAYERS, MIN_PLAYERS},
+    game_move::{Move, Side, MAX_MOVES},
+    hand::Hand,
+    knowledge::Knowledge,
+    snake::Snake,
+    tile::MAX_PIP,
+};
+
+/// in the double-six set, see `Tile::index`
+const TILES: usize = 28;
+/// what an end of the snake can show
+const PIPS: usize = MAX_PIP as usize + 1;
+
+// where every block of the input starts
+/// a tile I hold
+const HAND: usize = 0;
+const LEFT_END: usize = HAND + TILES;
+const RIGHT_END: usize = LEFT_END + PIPS;
+/// a tile on the snake
+const SNAKE: usize = RIGHT_END + PIPS;
+/// one block of `OTHER` for every other seat in turn order
+const OTHERS: usize = SNAKE + TILES;
+/// the seat's hand size and then the tiles it could hold
+const OTHER: usize = 1 + TILES;
+
+/// own hand, both snake ends, the tiles on the snake, and for every other
+/// seat in turn order its hand size and the tiles it could hold
+pub const INPUT: usize = OTHERS + (MAX_PLAYERS - 1) * OTHER;
+/// a logit per tile and side
+pub const MOVES: usize = TILES * 2;
+/// the move logits, then the value
+pub const OUTPUT: usize = MOVES + 1;
+const HIDDEN: usize = 128;
+
+/// Starts every weights file.
+const MAGIC: &[u8; 4] = b"DMNN";
+/// How much the value's error counts next to the policy's.
+const VALUE_WEIGHT: f32 = 0.5;
+/// Added to the logits of illegal moves so they get no probability.
+const ILLEGAL: f32 = -1e9;
+
+type Model = (
+    Linear<INPUT, HIDDEN, f32, Cpu>,
+    ReLU,
+    Linear<HIDDEN, HIDDEN, f32, Cpu>,
+    ReLU,
+    Linear<HIDDEN, OUTPUT, f32, Cpu>,
+);
+
+/// Where `mv` sits among the move logits.
+pub fn move_index(mv: Move) -> usize {
+    let side = match mv.side {
+        Side::Left => 0,
+        Side::Right => 1,
+    };
+    mv.tile.index() * 2 + side
+}
+
+/// One move chosen by a network, and how the game went for the player
+/// who chose it.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub input: [f32; INPUT],
+    /// `move_index` of every legal move
+    pub legal: ArrayVec<usize, MAX_MOVES>,
+    pub chosen: usize,
+    /// 1 for a win, -1 for a loss and 0 when nobody won
+    pub reward: f32,
+}
+
+/// Adam, keeping its moments from one `Network::learn` to the next.
+pub struct Optimizer {
+    adam: Adam<Model, f32, Cpu>,
+}
+
+impl Optimizer {
+    pub fn new(network: &Network, learning_rate: f64) -> Optimizer {
+        let config = AdamConfig {
+            lr: learning_rate,
+            ..Default::default()
+        };
+        Optimizer {
+            adam: Adam::new(&network.model, config),
+        }
+    }
+}
+
+/// What the seat of `knowledge` can see, as the network's input.
+pub fn encode(knowledge: &Knowledge, hand: &Hand, snake: &Snake) -> [f32; INPUT] {
+    let mut input = [0.0; INPUT];
+    for tile in hand.tiles.iter() {
+        input[HAND + tile.index()] = 1.0;
+    }
+    if let (Some(left), Some(right)) = (snake.left(), snake.right()) {
+        input[LEFT_END + left as usize] = 1.0;
+        input[RIGHT_END + right as usize] = 1.0;
+    }
+    for tile in snake.tiles.iter() {
+        input[SNAKE + tile.index()] = 1.0;
+    }
+
+    let players = knowledge.hands.len();
+    for offset in 1..players {
+        let possible = &knowledge.hands[(knowledge.seat + offset) % players];
+        let start = OTHERS + (offset - 1) * OTHER;
+        input[start] = possible.size as f32 / Game::hand_size(MIN_PLAYERS) as f32;
+        for tile in possible.possible_tiles.iter() {
+            input[start + 1 + tile.index()] = 1.0;
+        }
+    }
+    input
+}
+
+/// A small MLP run on the CPU, mapping what a seat sees to a logit for
+/// every move and a value in [-1, 1] for the position.
+#[derive(Clone)]
+pub struct Network {
+    device: Cpu,
+    model: Model,
+}
+
+impl Network {
+    /// Freshly initialized weights.
+    pub fn new(seed: u64) -> Network {
+        let device = Cpu::seed_from_u64(seed);
+        let model = Model::build(&device);
+        Network { device, model }
+    }
+
+    /// Reads weights written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Network> {
+        let bytes = fs::read(path)?;
+        let mut network = Network::new(0);
+        network.set_parameters(&Self::decode(&bytes)?)?;
+        Ok(network)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let parameters = self.parameters();
+        let mut bytes = Vec::with_capacity(8 + parameters.len() * 4);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&(parameters.len() as u32).to_le_bytes());
+        for parameter in parameters {
+            bytes.extend_from_slice(&parameter.to_le_bytes());
+        }
+        fs::write(path, bytes)
+    }
+
+    fn decode(bytes: &[u8]) -> io::Result<Vec<f32>> {
+        let invalid = |message: &str| io::Error::new(ErrorKind::InvalidData, message.to_string());
+        if bytes.len() < 8 || &bytes[..4] != MAGIC {
+            return Err(invalid("not a weights file"));
+        }
+        let count = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        if bytes.len() != 8 + count * 4 {
+            return Err(invalid("the weights file doesn't match its weight count"));
+        }
+        Ok(bytes[8..]
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect())
+    }
+
+    /// Every weight and bias, layer by layer.
+    pub fn parameters(&self) -> Vec<f32> {
+        let (first, _, second, _, third) = &self.model;
+        [
+            first.weight.as_vec(),
+            first.bias.as_vec(),
+            second.weight.as_vec(),
+            second.bias.as_vec(),
+            third.weight.as_vec(),
+            third.bias.as_vec(),
+        ]
+        .concat()
+    }
+
+    /// The inverse of `parameters`.
+    pub fn set_parameters(&mut self, parameters: &[f32]) -> io::Result<()> {
+        fn take<S: Shape>(tensor: &mut Tensor<S, f32, Cpu>, parameters: &mut &[f32]) {
+            let (taken, rest) = parameters.split_at(tensor.shape().num_elements());
+            tensor.copy_from(taken);
+            *parameters = rest;
+        }
+
+        if parameters.len() != self.parameters().len() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("expected {} weights, found {}", self.parameters().len(), parameters.len()),
+            ));
+        }
+        let mut parameters = parameters;
+        let (first, _, second, _, third) = &mut self.model;
+        take(&mut first.weight, &mut parameters);
+        take(&mut first.bias, &mut parameters);
+        take(&mut second.weight, &mut parameters);
+        take(&mut second.bias, &mut parameters);
+        take(&mut third.weight, &mut parameters);
+        take(&mut third.bias, &mut parameters);
+        Ok(())
+    }
+
+    /// The move logits and the value.
+    pub fn evaluate(&self, input: &[f32; INPUT]) -> ([f32; MOVES], f32) {
+        let input: Tensor<Rank1<INPUT>, f32, Cpu> = self.device.tensor(*input);
+        let output = self.model.forward(input).array();
+        let mut logits = [0.0; MOVES];
+        logits.copy_from_slice(&output[..MOVES]);
+        (logits, output[MOVES].tanh())
+    }
+
+    /// One step of policy gradient over `samples`, with the value as the
+    /// baseline the rewards are measured against. Returns the loss.
+    pub fn learn(&mut self, optimizer: &mut Optimizer, samples: &[Sample]) -> f32 {
+        let batch = samples.len();
+        let inputs = samples.iter().flat_map(|sample| sample.input).collect();
+        let inputs = self.device.tensor_from_vec(inputs, (batch, Const::<INPUT>));
+        let mut mask = vec![ILLEGAL; batch * OUTPUT];
+        for (row, sample) in samples.iter().enumerate() {
+            for index in sample.legal.iter() {
+                mask[row * OUTPUT + index] = 0.0;
+            }
+        }
+        let mask = self.device.tensor_from_vec(mask, (batch, Const::<OUTPUT>));
+        let chosen = samples.iter().map(|sample| sample.chosen).collect();
+        let chosen = self.device.tensor_from_vec(chosen, (batch,));
+        let value_index = self.device.tensor_from_vec(vec![MOVES; batch], (batch,));
+        let rewards: Vec<f32> = samples.iter().map(|sample| sample.reward).collect();
+
+        let grads = self.model.alloc_grads();
+        let output = self.model.forward(inputs.traced(grads));
+        let value = output.with_empty_tape().select(value_index).tanh();
+        let advantages = rewards.iter().zip(value.as_vec()).map(|(reward, value)| reward - value).collect();
+        let advantages = self.device.tensor_from_vec(advantages, (batch,));
+        let log_probs = (output + mask).log_softmax::<Axis<1>>().select(chosen);
+        let policy_loss = -(log_probs * advantages).mean();
+        let value_loss = mse_loss(value, self.device.tensor_from_vec(rewards, (batch,)));
+        let loss = policy_loss + value_loss * VALUE_WEIGHT;
+
+        let total = loss.array();
+        let grads = loss.backward();
+        optimizer.adam.update(&mut self.model, &grads).expect("every weight has a gradient");
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        encode, move_index, Network, Optimizer, Sample, HAND, INPUT, LEFT_END, OTHER, OTHERS, RIGHT_END, SNAKE,
+    };
+    use crate::{
+        game::GameMode,
+        game_move::{Move, Side},
+        game_observer::GameObserver,
+        hand::Hand,
+        knowledge::Knowledge,
+        snake::Snake,
+        tile::Tile,
+    };
+
+    #[test]
+    fn encodes_what_the_seat_can_see() {
+        let hand = Hand {
+            tiles: [Tile::new(0, 1), Tile::new(6, 6)].into_iter().collect(),
+        };
+        let mut snake = Snake::new();
+        snake.play(Tile::new(2, 5), Side::Right).unwrap();
+        let mut knowledge = Knowledge::default();
+        knowledge.game_started(1, &hand, &[7, 2], &snake, GameMode::Block, None);
+
+        let input = encode(&knowledge, &hand, &snake);
+
+        assert_eq!(input[HAND + Tile::new(0, 1).index()], 1.0);
+        assert_eq!(input[HAND + Tile::new(6, 6).index()], 1.0);
+        assert_eq!(input[LEFT_END + 2], 1.0);
+        assert_eq!(input[RIGHT_END + 5], 1.0);
+        assert_eq!(input[SNAKE + Tile::new(2, 5).index()], 1.0);
+        // seat 0 holds 7 of the 25 tiles that are neither mine nor on the snake
+        assert_eq!(input[OTHERS], 1.0);
+        assert_eq!(input[OTHERS + 1..OTHERS + OTHER].iter().sum::<f32>(), 25.0);
+        assert!(input[OTHERS + OTHER..].iter().all(|value| *value == 0.0));
+    }
+
+    #[test]
+    fn every_move_has_its_own_logit() {
+        let tile = Tile::new(3, 4);
+        let left = move_index(Move::new(tile, Side::Left));
+        let right = move_index(Move::new(tile, Side::Right));
+        assert_ne!(left, right);
+        assert_eq!(left / 2, right / 2);
+    }
+
+    #[test]
+    fn saved_weights_load_back() {
+        let network = Network::new(7);
+        let path = std::env::temp_dir().join(format!("rust_domino_weights_{}", std::process::id()));
+        network.save(&path).unwrap();
+        let loaded = Network::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let input = [0.5; INPUT];
+        assert_eq!(network.evaluate(&input), loaded.evaluate(&input));
+        assert_ne!(Network::new(8).evaluate(&input), loaded.evaluate(&input));
+    }
+
+    #[test]
+    fn learning_makes_a_rewarded_move_likelier() {
+        let mut network = Network::new(3);
+        let mut optimizer = Optimizer::new(&network, 1e-3);
+        let input = [1.0; INPUT];
+        let probability = |network: &Network| {
+            let (logits, _) = network.evaluate(&input);
+            let [a, b] = [logits[4], logits[9]].map(f32::exp);
+            a / (a + b)
+        };
+        let sample = Sample {
+            input,
+            legal: [4, 9].into_iter().collect(),
+            chosen: 4,
+            reward: 1.0,
+        };
+
+        let before = probability(&network);
+        for _ in 0..10 {
+            network.learn(&mut optimizer, std::slice::from_ref(&sample));
+        }
+        assert!(probability(&network) > before);
+    }
+
+    #[test]
+    fn rejects_a_file_that_isnt_weights() {
+        let path = std::env::temp_dir().join(format!("rust_domino_not_weights_{}", std::process::id()));
+        std::fs::write(&path, b"DMNN\x03\0\0\0").unwrap();
+        assert!(Network::load(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
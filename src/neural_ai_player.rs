@@ -0,0 +1,179 @@
+use std::{cell::RefCell, io, path::Path, rc::Rc};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    choose_tile_strategy::ChooseTileStrategy,
+    game::GameMode,
+    game_move::{legal_moves, Move},
+    game_observer::GameObserver,
+    hand::Hand,
+    knowledge::Knowledge,
+    network::{encode, move_index, Network, Sample},
+    player::Player,
+    snake::Snake,
+    tile::Tile,
+};
+
+/// The moves a player chose in one game, shared with whoever trains on them.
+pub type Trajectory = Rc<RefCell<Vec<Sample>>>;
+
+/// Plays the legal move its `Network` gives the highest logit, or, while
+/// exploring, samples one by the network's probabilities.
+pub struct NeuralAIPlayer {
+    network: Network,
+    knowledge: Knowledge,
+    exploration: Option<StdRng>,
+    trajectory: Option<Trajectory>,
+}
+
+impl NeuralAIPlayer {
+    pub fn new(network: Network) -> Self {
+        NeuralAIPlayer {
+            network,
+            knowledge: Knowledge::default(),
+            exploration: None,
+            trajectory: None,
+        }
+    }
+
+    /// Samples its moves instead of always playing the likeliest.
+    pub fn exploring(mut self, seed: u64) -> Self {
+        self.exploration = Some(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Adds every move it chooses to `trajectory`, rewards left at 0.
+    pub fn recording(mut self, trajectory: Trajectory) -> Self {
+        self.trajectory = Some(trajectory);
+        self
+    }
+
+    /// With the weights in a file written by `Network::save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(Network::load(path)?))
+    }
+}
+
+impl Player for NeuralAIPlayer {}
+
+impl ChooseTileStrategy for NeuralAIPlayer {
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        let moves = legal_moves(hand, snake);
+        if moves.len() == 1 && self.trajectory.is_none() {
+            return moves[0];
+        }
+        let input = encode(&self.knowledge, hand, snake);
+        let (logits, _) = self.network.evaluate(&input);
+        let logits: Vec<f32> = moves.iter().map(|mv| logits[move_index(*mv)]).collect();
+
+        let chosen = match self.exploration.as_mut() {
+            Some(rng) => {
+                let highest = logits.iter().copied().fold(f32::MIN, f32::max);
+                let weights: Vec<f32> = logits.iter().map(|logit| (logit - highest).exp()).collect();
+                let mut left = rng.gen::<f32>() * weights.iter().sum::<f32>();
+                // rounding may leave a little over for the last move
+                weights
+                    .iter()
+                    .position(|weight| {
+                        left -= weight;
+                        left <= 0.0
+                    })
+                    .unwrap_or(moves.len() - 1)
+            }
+            None => (0..moves.len()).max_by(|a, b| logits[*a].total_cmp(&logits[*b])).unwrap(),
+        };
+
+        if let Some(trajectory) = &self.trajectory {
+            trajectory.borrow_mut().push(Sample {
+                input,
+                legal: moves.iter().map(|mv| move_index(*mv)).collect(),
+                chosen: move_index(moves[chosen]),
+                reward: 0.0,
+            });
+        }
+        moves[chosen]
+    }
+}
+
+impl GameObserver for NeuralAIPlayer {
+    fn game_started(
+        &mut self,
+        seat: usize,
+        hand: &Hand,
+        hand_sizes: &[usize],
+        snake: &Snake,
+        game_mode: GameMode,
+        partner: Option<usize>,
+    ) {
+        self.knowledge.game_started(seat, hand, hand_sizes, snake, game_mode, partner);
+    }
+
+    fn opponent_drew_tile(&mut self, seat: usize) {
+        self.knowledge.opponent_drew_tile(seat);
+    }
+
+    fn i_drew_tile(&mut self, tile: Tile) {
+        self.knowledge.i_drew_tile(tile);
+    }
+
+    fn opponent_played_tile(&mut self, seat: usize, tile: Tile) {
+        self.knowledge.opponent_played_tile(seat, tile);
+    }
+
+    fn opponent_was_blocked(&mut self, seat: usize, pips: [u8; 2]) {
+        self.knowledge.opponent_was_blocked(seat, pips);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NeuralAIPlayer, Trajectory};
+    use crate::{
+        first_possible_tile_ai_player::FirstPossibleTileAIPlayer,
+        game::{Game, GameState},
+        game_move::Action,
+        network::Network,
+        player::Player,
+    };
+
+    #[test]
+    fn plays_whole_games_with_untrained_weights() {
+        for seed in 0..5 {
+            let players: Vec<Box<dyn Player>> = vec![
+                Box::new(NeuralAIPlayer::new(Network::new(seed))),
+                Box::new(FirstPossibleTileAIPlayer),
+            ];
+            let mut game = Game::from_seed(seed, players);
+            loop {
+                let (next, state) = game.play();
+                game = next;
+                if let GameState::Finished { .. } = state {
+                    break;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn records_every_move_it_explores() {
+        let trajectory = Trajectory::default();
+        let player = NeuralAIPlayer::new(Network::new(1)).exploring(1).recording(trajectory.clone());
+        let players: Vec<Box<dyn Player>> = vec![Box::new(player), Box::new(FirstPossibleTileAIPlayer)];
+        let mut game = Game::from_seed(1, players);
+        loop {
+            let (next, state) = game.play();
+            game = next;
+            if let GameState::Finished { .. } = state {
+                break;
+            }
+        }
+
+        let plays = game.record().events.iter().filter(|event| {
+            event.seat() == 0 && matches!(event.action(), Action::Play(_))
+        });
+        let samples = trajectory.borrow();
+        assert_eq!(samples.len(), plays.count());
+        assert!(samples.iter().all(|sample| sample.legal.contains(&sample.chosen)));
+    }
+}
@@ -1,58 +1,108 @@
-use crate::{hand::{Hand, HasHandTrait}, snake::Snake, tile::Tile, player::Player, choose_tile_strategy::ChooseTileStrategy, game_observer::GameObserver, possible_hand::PossibleHand};
+use crate::{game::GameMode, hand::Hand, snake::Snake, tile::Tile, player::Player, choose_tile_strategy::ChooseTileStrategy, game_move::{legal_moves, Move}, game_observer::GameObserver, knowledge::Knowledge};
 
+/// What leaving an opponent without a tile to play is worth, in pips.
+const BLOCKING: f64 = 8.0;
+
+/// Dumps its heaviest tiles like `GreedyAIPlayer`, but rather leaves the snake
+/// on ends the opponents are unlikely to hold, going by what they were seen
+/// to play and pass on.
 #[derive(Default)]
 pub struct ObserventAIPlayer {
-	pub hand: Hand,
-    opponent_hand: PossibleHand,
+    knowledge: Knowledge,
 }
 
-impl Player for ObserventAIPlayer {}
+impl ObserventAIPlayer {
+    /// Pips dumped plus the chance each opponent can't follow, less the
+    /// chance the partner can't.
+    fn score(&self, mv: Move, snake: &Snake) -> f64 {
+        let mut snake = snake.clone();
+        snake.play(mv.tile, mv.side).unwrap();
 
-impl HasHandTrait for ObserventAIPlayer {
-    fn hand(&self) -> &Hand {
-        &self.hand
-    }
-    fn hand_mut(&mut self) -> &mut Hand {
-        &mut self.hand
+        let knowledge = &self.knowledge;
+        let blocking: f64 = (0..knowledge.hands.len())
+            .filter(|seat| *seat != knowledge.seat)
+            .map(|seat| {
+                let blocked = knowledge.hands[seat].chance_without(|tile| snake.is_playable(*tile));
+                if Some(seat) == knowledge.partner { -blocked } else { blocked }
+            })
+            .sum();
+        mv.tile.score() as f64 + BLOCKING * blocking
     }
 }
 
+impl Player for ObserventAIPlayer {}
+
 impl ChooseTileStrategy for ObserventAIPlayer {
-    fn choose_tile(&mut self, snake: &Snake) -> Tile {
-        let tile_index = self.hand.tiles.iter().position(|tile| snake.is_playable(*tile)).unwrap();
-        self.hand.tiles.remove(tile_index)
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        legal_moves(hand, snake)
+            .into_iter()
+            .max_by(|a, b| self.score(*a, snake).total_cmp(&self.score(*b, snake)))
+            .unwrap()
     }
 }
 
 impl GameObserver for ObserventAIPlayer {
-    // called after deal
-    fn game_started(&mut self) {
-        // remove my tiles from opponent's possible hand
-        for tile in self.hand.tiles.iter() {
-            self.opponent_hand.remove_tile(*tile);
-        }
-        self.opponent_hand.size = self.hand.tiles.len() as u8;
+    fn game_started(&mut self, seat: usize, hand: &Hand, hand_sizes: &[usize], snake: &Snake, game_mode: GameMode, partner: Option<usize>) {
+        self.knowledge.game_started(seat, hand, hand_sizes, snake, game_mode, partner);
     }
 
-	fn opponent_drew_tile(&mut self) {
-        self.opponent_hand.size += 1;
+    fn opponent_drew_tile(&mut self, seat: usize) {
+        self.knowledge.opponent_drew_tile(seat);
     }
 
     fn i_drew_tile(&mut self, tile: Tile) {
-        // remove my tile from opponent's possible hand
-        self.opponent_hand.remove_tile(tile);
+        self.knowledge.i_drew_tile(tile);
+    }
+
+    fn opponent_played_tile(&mut self, seat: usize, tile: Tile) {
+        self.knowledge.opponent_played_tile(seat, tile);
+    }
+
+    fn opponent_was_blocked(&mut self, seat: usize, pips: [u8;2]) {
+        self.knowledge.opponent_was_blocked(seat, pips);
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ObserventAIPlayer;
+    use crate::{
+        choose_tile_strategy::ChooseTileStrategy,
+        game::GameMode,
+        game_move::{Move, Side},
+        game_observer::GameObserver,
+        hand::Hand,
+        snake::Snake,
+        tile::Tile,
+    };
 
-	fn opponent_played_tile(&mut self, tile: Tile) {
-        self.opponent_hand.remove_tile(tile);
-        self.opponent_hand.size -= 1;
+    #[test]
+    fn leaves_an_end_the_opponent_passed_on() {
+        let mut player = ObserventAIPlayer::default();
+        let hand = Hand {
+            tiles: [Tile::new(2, 6), Tile::new(2, 1)].into_iter().collect(),
+        };
+        let mut snake = Snake::new();
+        snake.play(Tile::new(2, 2), Side::Right).unwrap();
+        player.game_started(0, &hand, &[2, 5], &snake, GameMode::Block, None);
+        // seat 1 has no 1 and no 2 left, but may well have a 6
+        player.opponent_was_blocked(1, [1, 2]);
 
-        println!("opponents possible hand size: {}", self.opponent_hand.size);
-        println!("opponents possible hand: {:?}", self.opponent_hand.possible_tiles);
-        println!("opponents possible hand: {:?}", self.opponent_hand.possible_tiles.len());
+        assert_eq!(player.choose_move(&hand, &snake), Move::new(Tile::new(2, 1), Side::Right));
     }
 
-	fn opponent_was_blocked(&mut self, pips: [u8;2]) {
-        self.opponent_hand.remove_tiles_with_pips(pips);
+    #[test]
+    fn leaves_an_end_its_partner_can_follow() {
+        let mut player = ObserventAIPlayer::default();
+        let hand = Hand {
+            tiles: [Tile::new(2, 6), Tile::new(2, 1)].into_iter().collect(),
+        };
+        let mut snake = Snake::new();
+        snake.play(Tile::new(2, 2), Side::Right).unwrap();
+        player.game_started(0, &hand, &[2, 5, 5, 5], &snake, GameMode::Block, Some(2));
+        // seat 2 has no 2 and no 6 left, [2|6] would block them
+        player.partner_was_blocked(2, [2, 6]);
+
+        assert_eq!(player.choose_move(&hand, &snake), Move::new(Tile::new(2, 1), Side::Right));
     }
 }
@@ -1,5 +1,6 @@
-use crate::{hand::HasHandTrait, choose_tile_strategy::ChooseTileStrategy, game_observer::GameObserver};
+use crate::{choose_tile_strategy::ChooseTileStrategy, game_observer::GameObserver};
 
-pub trait Player: HasHandTrait + ChooseTileStrategy + GameObserver + Default {
+/// A seat at the table. The game keeps every hand and shows each player its own.
+pub trait Player: ChooseTileStrategy + GameObserver {
 
 }
@@ -0,0 +1,497 @@
+use std::fmt;
+
+use arrayvec::ArrayVec;
+use rand::Rng;
+
+use crate::{
+    boneyard::Boneyard,
+    game::{Game, GameMode, GameState, Winner, MAX_PLAYERS, MIN_PLAYERS},
+    game_move::{legal_actions, Action, MAX_MOVES},
+    hand::{Hand, HandTrait},
+    record::Event,
+    snake::Snake,
+    tile::{Tile, MAX_PIP},
+};
+
+/// The table between two turns: every tile, whose turn it is and the rules in
+/// play. `Game` plays one out by asking its players for their moves, anything
+/// that looks ahead can play out a copy with `apply`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// indexed by seat
+    pub hands: ArrayVec<Hand, MAX_PLAYERS>,
+    /// drawn from the back
+    pub boneyard: Boneyard,
+    pub snake: Snake,
+    pub current_player: usize,
+    pub game_mode: GameMode,
+    /// see `Game::partnership`
+    pub partnership: bool,
+    /// how many seats in a row passed
+    pub blocked_counter: u8,
+    /// what `undo` takes back, oldest first
+    pub history: Vec<Step>,
+}
+
+/// An applied event with what it overwrote, see `Position::undo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    pub event: Event,
+    pub blocked_counter: u8,
+    /// where a played tile sat in the hand
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionError {
+    PlayerCount(usize),
+    /// the position has a different number of hands than there are players
+    SeatCount { hands: usize, players: usize },
+    CurrentPlayer(usize),
+    /// a pip above the highest of the set
+    UnknownTile(Tile),
+    DuplicateTile(Tile),
+    /// neighbouring tiles on the snake don't match
+    InvalidSnake,
+    /// partnership play with other than four players
+    Partnership(usize),
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PositionError::PlayerCount(count) => write!(
+                f,
+                "a game needs between {} and {} players, not {}",
+                MIN_PLAYERS, MAX_PLAYERS, count
+            ),
+            PositionError::SeatCount { hands, players } => {
+                write!(f, "{} hands for {} players", hands, players)
+            }
+            PositionError::CurrentPlayer(seat) => write!(f, "there is no seat {}", seat),
+            PositionError::UnknownTile(tile) => write!(f, "{:?} is not in the set", tile),
+            PositionError::DuplicateTile(tile) => write!(f, "{:?} is on the table twice", tile),
+            PositionError::InvalidSnake => write!(f, "the snake's tiles don't match up"),
+            PositionError::Partnership(count) => {
+                write!(f, "partnership play needs four players, not {}", count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+impl Position {
+    /// Seats a hand each in a block game, nobody having passed yet. Panics
+    /// with more than `MAX_PLAYERS` hands.
+    pub fn new(
+        hands: impl IntoIterator<Item = Hand>,
+        boneyard: Boneyard,
+        snake: Snake,
+        current_player: usize,
+    ) -> Position {
+        Position {
+            hands: hands.into_iter().collect(),
+            boneyard,
+            snake,
+            current_player,
+            game_mode: GameMode::Block,
+            partnership: false,
+            blocked_counter: 0,
+            history: Vec::new(),
+        }
+    }
+
+    /// Shuffles a full set and deals every seat `Game::hand_size` tiles,
+    /// seat 0 to move.
+    pub fn deal<R: Rng + ?Sized>(rng: &mut R, player_count: usize) -> Position {
+        Self::deal_set(rng, player_count, MAX_PIP, Game::hand_size(player_count))
+    }
+
+    /// Deals `hand_size` tiles each from the double-`max_pip` set.
+    pub fn deal_set<R: Rng + ?Sized>(rng: &mut R, player_count: usize, max_pip: u8, hand_size: usize) -> Position {
+        let mut boneyard = Boneyard::with_max_pip(max_pip).shuffle(rng);
+        assert!(
+            boneyard.tiles.len() >= player_count * hand_size,
+            "not enough tiles to deal"
+        );
+
+        let hands: ArrayVec<Hand, MAX_PLAYERS> = (0..player_count)
+            .map(|_| {
+                let mut hand = Hand::default();
+                hand.add_multiple(boneyard.draw_n(hand_size as i32));
+                hand
+            })
+            .collect();
+        Position::new(hands, boneyard, Snake::new(), 0)
+    }
+
+    /// Tiles don't all have to be on the table, but none may be there twice.
+    pub fn validate(&self) -> Result<(), PositionError> {
+        self.validate_set(MAX_PIP)
+    }
+
+    /// Like `validate`, for a position dealt from the double-`max_pip` set,
+    /// see `deal_set`.
+    pub fn validate_set(&self, max_pip: u8) -> Result<(), PositionError> {
+        assert!(max_pip <= MAX_PIP, "sets go up to double-{}", MAX_PIP);
+        let player_count = self.hands.len();
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&player_count) {
+            return Err(PositionError::PlayerCount(player_count));
+        }
+        if self.current_player >= player_count {
+            return Err(PositionError::CurrentPlayer(self.current_player));
+        }
+        if self.partnership && player_count != 4 {
+            return Err(PositionError::Partnership(player_count));
+        }
+
+        let mut seen = [[false; MAX_PIP as usize + 1]; MAX_PIP as usize + 1];
+        let tiles = self
+            .hands
+            .iter()
+            .flat_map(|hand| hand.tiles.iter())
+            .chain(self.boneyard.tiles.iter())
+            .chain(self.snake.tiles.iter());
+        for tile in tiles {
+            if tile.left > max_pip || tile.right > max_pip {
+                return Err(PositionError::UnknownTile(*tile));
+            }
+            let tile = tile.normalized();
+            let seen = &mut seen[tile.left as usize][tile.right as usize];
+            if *seen {
+                return Err(PositionError::DuplicateTile(tile));
+            }
+            *seen = true;
+        }
+
+        if !self.snake.is_valid() {
+            return Err(PositionError::InvalidSnake);
+        }
+        Ok(())
+    }
+
+    pub fn partner(&self, seat: usize) -> Option<usize> {
+        self.partnership.then_some((seat + 2) % 4)
+    }
+
+    pub fn can_draw(&self) -> bool {
+        self.game_mode == GameMode::Draw && !self.boneyard.is_empty()
+    }
+
+    /// What the current player may do, see `game_move::legal_actions`.
+    pub fn legal_actions(&self) -> ArrayVec<Action, MAX_MOVES> {
+        legal_actions(&self.hands[self.current_player], &self.snake, self.can_draw())
+    }
+
+    /// Takes one of `legal_actions` for the current player and returns what
+    /// happened. The turn passes on unless the player drew or went out.
+    ///
+    /// Panics when a played tile is not in the hand or does not fit.
+    pub fn apply(&mut self, action: Action) -> Event {
+        let seat = self.current_player;
+        let blocked_counter = self.blocked_counter;
+        let mut index = 0;
+        let event = match action {
+            Action::Draw => {
+                let tile = self.boneyard.draw();
+                self.hands[seat].add(tile);
+                Event::Draw { seat, tile }
+            }
+            Action::Pass => {
+                // nobody is blocked by an empty snake, any tile fits
+                assert!(!self.snake.is_empty(), "seat {} passed on an empty snake", seat);
+                self.blocked_counter += 1;
+                self.next_player();
+                Event::Pass { seat }
+            }
+            Action::Play(mv) => {
+                index = match self.hands[seat].tiles.iter().position(|tile| *tile == mv.tile) {
+                    Some(index) => index,
+                    None => panic!("seat {} does not hold {:?}", seat, mv.tile),
+                };
+                if let Err(error) = self.snake.play(mv.tile, mv.side) {
+                    panic!("seat {} made an illegal move: {}", seat, error);
+                }
+                self.hands[seat].tiles.remove(index);
+                // a tile on the snake gives everyone new ends to play on
+                self.blocked_counter = 0;
+                if !self.hands[seat].is_empty() {
+                    self.next_player();
+                }
+                Event::Play { seat, mv }
+            }
+        };
+
+        self.history.push(Step {
+            event,
+            blocked_counter,
+            index,
+        });
+        event
+    }
+
+    /// Takes back the last applied event and returns it, leaving the position
+    /// exactly as it was before. Drawn tiles go back on top of the boneyard.
+    pub fn undo(&mut self) -> Option<Event> {
+        let step = self.history.pop()?;
+        match step.event {
+            Event::Draw { seat, tile } => {
+                let drawn = self.hands[seat].tiles.pop();
+                debug_assert_eq!(drawn, Some(tile));
+                self.boneyard.put_back(tile);
+            }
+            Event::Play { seat, mv } => {
+                self.snake.take_back(mv.side);
+                self.hands[seat].tiles.insert(step.index, mv.tile);
+            }
+            Event::Pass { .. } => {}
+        }
+        self.blocked_counter = step.blocked_counter;
+        self.current_player = step.event.seat();
+        Some(step.event)
+    }
+
+    fn next_player(&mut self) {
+        self.current_player = (self.current_player + 1) % self.hands.len();
+    }
+
+    /// `GameState::Finished` once the current player went out or every seat
+    /// passed in a row.
+    pub fn state(&self) -> GameState {
+        if self.hands[self.current_player].is_empty() {
+            self.finished(Some(self.current_player))
+        } else if self.blocked_counter as usize == self.hands.len() {
+            self.finished(None)
+        } else {
+            GameState::Playing
+        }
+    }
+
+    /// `went_out` is the seat that played its last tile, `None` for a blocked game.
+    fn finished(&self, went_out: Option<usize>) -> GameState {
+        let hand_scores: ArrayVec<i32, MAX_PLAYERS> = self.hands.iter().map(|hand| hand.score()).collect();
+
+        let winner = if self.partnership {
+            let team_scores = [hand_scores[0] + hand_scores[2], hand_scores[1] + hand_scores[3]];
+            match went_out {
+                Some(seat) => Some(Winner::Team(Game::team(seat))),
+                None => Self::lowest(&team_scores).map(Winner::Team),
+            }
+        } else {
+            match went_out {
+                Some(seat) => Some(Winner::Seat(seat)),
+                None => Self::lowest(&hand_scores).map(Winner::Seat),
+            }
+        };
+
+        // the winner gets the score of the opponents added to their score
+        let total: i32 = hand_scores.iter().sum();
+        let scores = (0..hand_scores.len())
+            .map(|seat| match (winner, self.partner(seat)) {
+                (Some(winner), _) if winner.contains(seat) => total,
+                (_, Some(partner)) => hand_scores[seat] + hand_scores[partner],
+                (_, None) => hand_scores[seat],
+            })
+            .collect();
+
+        GameState::Finished { winner, scores }
+    }
+
+    /// The index of the lowest score, unless it is shared.
+    fn lowest(scores: &[i32]) -> Option<usize> {
+        let lowest = *scores.iter().min().unwrap();
+        let mut lowest_indices = (0..scores.len()).filter(|index| scores[*index] == lowest);
+        match (lowest_indices.next(), lowest_indices.next()) {
+            (Some(index), None) => Some(index),
+            _ => None,
+        }
+    }
+}
+
+/// Hands and positions for the tests of every module that plays one out.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use arrayvec::ArrayVec;
+
+    use super::Position;
+    use crate::{
+        boneyard::Boneyard,
+        game_move::{Move, Side},
+        hand::Hand,
+        snake::Snake,
+        tile::Tile,
+    };
+
+    pub fn hand(tiles: &[(u8, u8)]) -> Hand {
+        Hand {
+            tiles: tiles.iter().map(|&(left, right)| Tile::new(left, right)).collect(),
+        }
+    }
+
+    /// Seat 0 to move in a block game with nothing to draw. The snake is
+    /// laid out as given, whether its tiles match or not.
+    pub fn position(hands: &[&[(u8, u8)]], snake: &[(u8, u8)]) -> Position {
+        let mut position = Position::new(
+            hands.iter().map(|tiles| hand(tiles)),
+            Boneyard { tiles: ArrayVec::new() },
+            Snake::new(),
+            0,
+        );
+        position.snake.tiles.extend(hand(snake).tiles);
+        position
+    }
+
+    /// Seat 0 holds [5|2] [3|0] [2|2] on a snake of [3|5] against [5|6]
+    /// [0|1]. [5|2] leaves a 2 for [2|2]; [3|0] lets seat 1 play [5|6] and
+    /// go out. Going out first wins seat 0 the 12 pips seat 1 still holds.
+    pub fn go_out_first() -> Position {
+        position(&[&[(5, 2), (3, 0), (2, 2)], &[(5, 6), (0, 1)]], &[(3, 5)])
+    }
+
+    /// The only move that wins `go_out_first`.
+    pub fn going_out_first() -> Move {
+        Move::new(Tile::new(5, 2), Side::Right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{fixtures::position, Position, PositionError};
+    use crate::{
+        game::{GameMode, GameState, Winner},
+        game_move::{Action, Move, Side},
+        record::Event,
+        tile::Tile,
+    };
+
+    #[test]
+    fn a_fresh_deal_is_valid() {
+        for player_count in 2..=4 {
+            let position = Position::deal(&mut rand::thread_rng(), player_count);
+            assert_eq!(position.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn accepts_a_partial_set() {
+        let position = position(&[&[(0, 1)], &[(2, 2)]], &[(3, 4), (4, 4)]);
+        assert_eq!(position.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_tile_held_twice() {
+        let position = position(&[&[(0, 1)], &[(1, 0)]], &[]);
+        assert_eq!(position.validate(), Err(PositionError::DuplicateTile(Tile::new(0, 1))));
+    }
+
+    #[test]
+    fn rejects_a_tile_both_held_and_on_the_snake() {
+        let position = position(&[&[(0, 1)], &[(2, 4)]], &[(4, 2), (2, 2)]);
+        assert_eq!(position.validate(), Err(PositionError::DuplicateTile(Tile::new(2, 4))));
+    }
+
+    #[test]
+    fn rejects_an_impossible_snake() {
+        let position = position(&[&[(0, 1)], &[(2, 2)]], &[(3, 4), (5, 5)]);
+        assert_eq!(position.validate(), Err(PositionError::InvalidSnake));
+    }
+
+    #[test]
+    fn rejects_unknown_tiles_and_seats() {
+        let position = position(&[&[(0, 7)], &[(2, 2)]], &[]);
+        assert_eq!(position.validate(), Err(PositionError::UnknownTile(Tile::new(0, 7))));
+
+        let mut position = self::position(&[&[(0, 1)], &[(2, 2)]], &[]);
+        position.current_player = 2;
+        assert_eq!(position.validate(), Err(PositionError::CurrentPlayer(2)));
+
+        let position = self::position(&[&[(0, 1)]], &[]);
+        assert_eq!(position.validate(), Err(PositionError::PlayerCount(1)));
+    }
+
+    #[test]
+    fn rejects_a_partnership_without_four_players() {
+        let mut position = position(&[&[(0, 1)], &[(2, 2)]], &[]);
+        position.partnership = true;
+        assert_eq!(position.validate(), Err(PositionError::Partnership(2)));
+    }
+
+    #[test]
+    fn deals_from_a_smaller_set() {
+        let position = Position::deal_set(&mut StdRng::seed_from_u64(1), 2, 3, 4);
+
+        assert_eq!(position.boneyard.tiles.len(), 2);
+        let tiles = position.hands.iter().flat_map(|hand| hand.tiles.iter()).chain(position.boneyard.tiles.iter());
+        assert!(tiles.clone().all(|tile| tile.left <= 3 && tile.right <= 3));
+        assert_eq!(tiles.count(), 10);
+        assert_eq!(position.validate_set(3), Ok(()));
+
+        let mut position = position;
+        position.boneyard.tiles.push(Tile::new(2, 4));
+        assert_eq!(position.validate(), Ok(()));
+        assert_eq!(position.validate_set(3), Err(PositionError::UnknownTile(Tile::new(2, 4))));
+    }
+
+    #[test]
+    fn applying_actions_plays_out_the_turns() {
+        let mut position = position(&[&[(4, 6), (1, 1)], &[(0, 2)]], &[(3, 4)]);
+        position.game_mode = GameMode::Draw;
+        position.boneyard.tiles.push(Tile::new(2, 3));
+
+        let mv = Move::new(Tile::new(4, 6), Side::Right);
+        assert_eq!(position.legal_actions().as_slice(), &[Action::Play(mv)]);
+        assert_eq!(position.apply(Action::Play(mv)), Event::Play { seat: 0, mv });
+        assert_eq!(position.current_player, 1);
+
+        // seat 1 draws and keeps the turn
+        assert_eq!(position.legal_actions().as_slice(), &[Action::Draw]);
+        let event = position.apply(Action::Draw);
+        assert_eq!(event, Event::Draw { seat: 1, tile: Tile::new(2, 3) });
+        assert_eq!(position.current_player, 1);
+        assert_eq!(position.state(), GameState::Playing);
+    }
+
+    #[test]
+    fn undo_restores_every_applied_action() {
+        let mut position = position(&[&[(4, 6), (1, 1)], &[(0, 2)]], &[(3, 4)]);
+        position.game_mode = GameMode::Draw;
+        position.boneyard.tiles.extend([Tile::new(5, 5), Tile::new(2, 3)]);
+        position.blocked_counter = 1;
+        let start = position.clone();
+
+        let mut seen = vec![position.clone()];
+        while position.state() == GameState::Playing {
+            let action = position.legal_actions()[0];
+            position.apply(action);
+            seen.push(position.clone());
+        }
+
+        seen.pop();
+        while let Some(expected) = seen.pop() {
+            position.undo().unwrap();
+            assert_eq!(position, expected);
+        }
+        assert_eq!(position, start);
+        assert_eq!(position.undo(), None);
+    }
+
+    #[test]
+    fn the_game_is_over_once_everyone_passed() {
+        let mut position = position(&[&[(0, 1)], &[(2, 2)]], &[(3, 4)]);
+        position.apply(Action::Pass);
+        assert_eq!(position.state(), GameState::Playing);
+        position.apply(Action::Pass);
+        assert_eq!(
+            position.state(),
+            GameState::Finished {
+                winner: Some(Winner::Seat(0)),
+                scores: [5, 4].into_iter().collect(),
+            }
+        );
+    }
+}
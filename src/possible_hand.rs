@@ -1,8 +1,9 @@
 use arrayvec::ArrayVec;
-use bitarray::{self, BitArray};
 
 use crate::tile::Tile;
 
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PossibleHand {
 	// this holds a bit array of all possible tiles 
 	pub possible_tiles: ArrayVec<Tile, 28>,
@@ -33,9 +34,16 @@ impl PossibleHand {
 		PossibleHand { possible_tiles: tiles, size: 0 }
 	}
 
+	pub fn contains(&self, tile: Tile) -> bool {
+		self.possible_tiles.contains(&tile.normalized())
+	}
+
+	/// the tile may already be ruled out, e.g. by `remove_tiles_with_pips`
 	pub fn remove_tile(&mut self, tile: Tile) {
-		let index = self.possible_tiles.iter().position(|t| *t == tile).unwrap();
-		self.possible_tiles.remove(index);
+		let tile = tile.normalized();
+		if let Some(index) = self.possible_tiles.iter().position(|t| *t == tile) {
+			self.possible_tiles.remove(index);
+		}
 	}
 
 	pub fn remove_tiles_with_pips(&mut self, pips: [u8; 2] ) {
@@ -50,16 +58,51 @@ impl PossibleHand {
 		}
 	}
 
+	/// The chance that none of the tiles held is `wanted`, taking every
+	/// possible tile as equally likely.
+	pub fn chance_without(&self, wanted: impl Fn(&Tile) -> bool) -> f64 {
+		let candidates = self.possible_tiles.len();
+		let others = self.possible_tiles.iter().filter(|tile| !wanted(tile)).count();
+		let size = (self.size as usize).min(candidates);
+		if size > others {
+			return 0.0;
+		}
+		(0..size).map(|index| (others - index) as f64 / (candidates - index) as f64).product()
+	}
+
+}
+
+impl Default for PossibleHand {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 // make a  test
 #[cfg(test)]
 mod tests {
+    use arrayvec::ArrayVec;
+
+    use super::PossibleHand;
     use crate::tile::Tile;
 
+	#[test]
+	fn the_chance_of_holding_none_of_some_tiles() {
+		let possible = |tiles: usize, size: u8| PossibleHand {
+			possible_tiles: (0..tiles as u8).map(|pips| Tile::new(0, pips)).collect::<ArrayVec<_, 28>>(),
+			size,
+		};
+		let fours = |tile: &Tile| tile.right >= 4;
+
+		assert_eq!(possible(4, 3).chance_without(fours), 1.0);
+		assert_eq!(possible(7, 5).chance_without(fours), 0.0);
+		// 4 of 5 candidates and then 3 of 4 miss the one [0|4]
+		assert!((possible(5, 2).chance_without(fours) - 0.6).abs() < 1e-9);
+	}
+
 	#[test]
 	fn test_possible_hand() {
-		let mut tiles = [ 
+		let tiles = [ 
 			Tile::new(0,0), Tile::new(0,1), Tile::new(0,2), Tile::new(0,3), Tile::new(0,4), Tile::new(0,5), Tile::new(0,6),
 			Tile::new(1,1), Tile::new(1,2), Tile::new(1,3), Tile::new(1,4), Tile::new(1,5), Tile::new(1,6),
 			Tile::new(2,2), Tile::new(2,3), Tile::new(2,4), Tile::new(2,5), Tile::new(2,6),
@@ -71,7 +114,7 @@ mod tests {
 
 		assert!(tiles.len() == 28);
 
-		for i in 0..tiles.len() {
+		for _i in 0..tiles.len() {
 			// let tile = tiles[i];
 			// let index = super::PossibleHand::tile_to_index(tile);
 			// assert!(index == i);
@@ -80,9 +123,3 @@ mod tests {
 		}
 	}
 }
-
-impl Default for PossibleHand {
-	fn default() -> Self {
-		Self::new()
-	}
-}
@@ -0,0 +1,582 @@
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+use arrayvec::ArrayVec;
+
+use crate::{
+    boneyard::Boneyard,
+    choose_tile_strategy::{Choice, ChooseTileStrategy},
+    game::{Game, GameMode, GameState, Winner, MAX_PLAYERS},
+    game_move::{legal_moves, Action, Move, Side},
+    game_observer::GameObserver,
+    hand::Hand,
+    player::Player,
+    position::{Position, PositionError},
+    snake::Snake,
+    tile::Tile,
+};
+
+/// Something that happened at the table, hidden tiles included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Event {
+    Draw { seat: usize, tile: Tile },
+    Play { seat: usize, mv: Move },
+    Pass { seat: usize },
+}
+
+impl Event {
+    /// The seat that drew, played or passed.
+    pub fn seat(&self) -> usize {
+        match *self {
+            Event::Draw { seat, .. } | Event::Play { seat, .. } | Event::Pass { seat } => seat,
+        }
+    }
+
+    /// What the seat did to cause the event, see `Position::apply`.
+    pub fn action(&self) -> Action {
+        match *self {
+            Event::Draw { .. } => Action::Draw,
+            Event::Play { mv, .. } => Action::Play(mv),
+            Event::Pass { .. } => Action::Pass,
+        }
+    }
+}
+
+/// A whole game: how it was set up and every event since. `Game` keeps one
+/// as it plays, see `Game::record`, rather than leaving it to a
+/// `GameObserver`: observers never see the tiles other seats draw.
+///
+/// The text notation has one entry per line:
+///
+/// ```text
+/// seed 42
+/// mode draw
+/// partnership
+/// hand 0 [0|1] [2|5]
+/// hand 1 [3|3] [1|4]
+/// boneyard [6|6] [0|0]
+/// snake
+/// turn 0
+/// play 0 [2|5] right
+/// draw 1 [0|0]
+/// pass 1
+/// result seat 0 scores 12 11
+/// ```
+///
+/// `seed` and `partnership` are optional, the boneyard is drawn from the
+/// back, and the result is either `seat <n>`, `team <n>` or `draw`
+/// followed by the score of every seat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRecord {
+    pub seed: Option<u64>,
+    /// the game mode and partnership are part of the start
+    pub start: Position,
+    pub events: Vec<Event>,
+    /// only ever `GameState::Finished`, once the game is over
+    pub result: Option<GameState>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRecordError {
+    /// 1-based
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseRecordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseRecordError {}
+
+impl GameRecord {
+    pub fn new(start: Position) -> GameRecord {
+        GameRecord {
+            seed: None,
+            start,
+            events: Vec::new(),
+            result: None,
+        }
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn game_finished(&mut self, state: &GameState) {
+        self.result = Some(state.clone());
+    }
+
+    /// A game at the recorded start whose players repeat the recorded moves,
+    /// to be stepped through with `Game::play`. Every event is checked
+    /// against the rules first, so the draws, passes and result the game
+    /// comes to on its own are the recorded ones. A record without a result,
+    /// like that of a game somebody quit, replays as far as it goes and the
+    /// game is then `Game::abandoned`.
+    pub fn replay(&self) -> Result<Game, ReplayError> {
+        let mut position = self.start.clone();
+        position.validate()?;
+        let mut moves: ArrayVec<VecDeque<Move>, MAX_PLAYERS> =
+            self.start.hands.iter().map(|_| VecDeque::new()).collect();
+        for (index, event) in self.events.iter().enumerate() {
+            check(&position, event).map_err(|reason| ReplayError::Event {
+                index,
+                event: *event,
+                reason,
+            })?;
+            if let Event::Play { seat, mv } = event {
+                moves[*seat].push_back(*mv);
+            }
+            position.apply(event.action());
+        }
+
+        let state = position.state();
+        if let Some(recorded) = &self.result {
+            if *recorded != state {
+                return Err(ReplayError::Result {
+                    recorded: recorded.clone(),
+                    replayed: state,
+                });
+            }
+        }
+
+        let players = moves
+            .into_iter()
+            .map(|moves| Box::new(ReplayPlayer { moves }) as Box<dyn Player>)
+            .collect();
+
+        let mut game = Game::from_position(self.start.clone(), players)?;
+        game.record_mut().seed = self.seed;
+        Ok(game)
+    }
+}
+
+/// Whether the current player of `position` may cause `event`.
+fn check(position: &Position, event: &Event) -> Result<(), EventError> {
+    if position.state() != GameState::Playing {
+        return Err(EventError::GameOver);
+    }
+    let seat = position.current_player;
+    if event.seat() != seat {
+        return Err(EventError::OutOfTurn(seat));
+    }
+    match (*event, position.legal_actions()[0]) {
+        (Event::Play { mv, .. }, _) if !position.hands[seat].tiles.contains(&mv.tile) => Err(EventError::NotHeld),
+        (Event::Play { mv, .. }, _) if !position.snake.fits(mv.tile, mv.side) => Err(EventError::DoesNotFit),
+        (Event::Play { .. }, _) => Ok(()),
+        (_, Action::Play(_)) => Err(EventError::MustPlay),
+        (Event::Draw { .. }, Action::Pass) => Err(EventError::MustPass),
+        (Event::Pass { .. }, Action::Draw) => Err(EventError::MustDraw),
+        (Event::Draw { tile, .. }, _) => match position.boneyard.tiles.last() {
+            Some(next) if *next != tile => Err(EventError::OtherTile(*next)),
+            _ => Ok(()),
+        },
+        (Event::Pass { .. }, _) => Ok(()),
+    }
+}
+
+/// Why an event can't happen where the record has it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventError {
+    GameOver,
+    /// it is the turn of this seat
+    OutOfTurn(usize),
+    NotHeld,
+    DoesNotFit,
+    /// a tile fits, so the seat can't draw or pass
+    MustPlay,
+    /// nothing fits and the boneyard has tiles left
+    MustDraw,
+    /// nothing fits and there is nothing to draw
+    MustPass,
+    /// the boneyard has this tile on top
+    OtherTile(Tile),
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventError::GameOver => write!(f, "the game is already over"),
+            EventError::OutOfTurn(seat) => write!(f, "it is seat {}'s turn", seat),
+            EventError::NotHeld => write!(f, "the seat doesn't hold that tile"),
+            EventError::DoesNotFit => write!(f, "the tile doesn't fit that end"),
+            EventError::MustPlay => write!(f, "a tile fits, so the seat has to play"),
+            EventError::MustDraw => write!(f, "nothing fits, so the seat has to draw"),
+            EventError::MustPass => write!(f, "nothing fits and there is nothing to draw, so the seat has to pass"),
+            EventError::OtherTile(tile) => write!(f, "the next tile in the boneyard is {:?}", tile),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    Position(PositionError),
+    /// the event at `index` breaks the rules
+    Event {
+        index: usize,
+        event: Event,
+        reason: EventError,
+    },
+    /// the events lead to another result than the recorded one
+    Result { recorded: GameState, replayed: GameState },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Position(error) => write!(f, "{}", error),
+            ReplayError::Event { index, event, reason } => {
+                write!(f, "event {} ({:?}) can't happen: {}", index + 1, event, reason)
+            }
+            ReplayError::Result { recorded, replayed } => {
+                write!(f, "the record says {:?}, but the game ends {:?}", recorded, replayed)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<PositionError> for ReplayError {
+    fn from(error: PositionError) -> Self {
+        ReplayError::Position(error)
+    }
+}
+
+/// Plays back one seat's moves from a record.
+struct ReplayPlayer {
+    moves: VecDeque<Move>,
+}
+
+impl Player for ReplayPlayer {}
+
+impl ChooseTileStrategy for ReplayPlayer {
+    /// `GameRecord::replay` made sure every recorded move is legal, the first
+    /// legal one only stands in to keep this from panicking.
+    fn choose_move(&mut self, hand: &Hand, snake: &Snake) -> Move {
+        self.moves.pop_front().unwrap_or_else(|| legal_moves(hand, snake)[0])
+    }
+
+    /// Quits where the record stops.
+    fn choose(&mut self, _hand: &Hand, _snake: &Snake) -> Choice {
+        self.moves.pop_front().map_or(Choice::Quit, Choice::Play)
+    }
+}
+
+impl GameObserver for ReplayPlayer {}
+
+fn write_tiles(f: &mut fmt::Formatter<'_>, label: &str, tiles: &[Tile]) -> fmt::Result {
+    write!(f, "{}", label)?;
+    for tile in tiles {
+        write!(f, " {:?}", tile)?;
+    }
+    writeln!(f)
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(seed) = self.seed {
+            writeln!(f, "seed {}", seed)?;
+        }
+        match self.start.game_mode {
+            GameMode::Draw => writeln!(f, "mode draw")?,
+            GameMode::Block => writeln!(f, "mode block")?,
+        }
+        if self.start.partnership {
+            writeln!(f, "partnership")?;
+        }
+        for (seat, hand) in self.start.hands.iter().enumerate() {
+            write_tiles(f, &format!("hand {}", seat), &hand.tiles)?;
+        }
+        write_tiles(f, "boneyard", &self.start.boneyard.tiles)?;
+        write_tiles(f, "snake", &self.start.snake.tiles)?;
+        writeln!(f, "turn {}", self.start.current_player)?;
+
+        for event in self.events.iter() {
+            match event {
+                Event::Draw { seat, tile } => writeln!(f, "draw {} {:?}", seat, tile)?,
+                Event::Play { seat, mv } => {
+                    let side = match mv.side {
+                        Side::Left => "left",
+                        Side::Right => "right",
+                    };
+                    writeln!(f, "play {} {:?} {}", seat, mv.tile, side)?
+                }
+                Event::Pass { seat } => writeln!(f, "pass {}", seat)?,
+            }
+        }
+
+        if let Some(GameState::Finished { winner, scores }) = &self.result {
+            match winner {
+                Some(Winner::Seat(seat)) => write!(f, "result seat {} scores", seat)?,
+                Some(Winner::Team(team)) => write!(f, "result team {} scores", team)?,
+                None => write!(f, "result draw scores")?,
+            }
+            for score in scores {
+                write!(f, " {}", score)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_number<T: FromStr>(word: Option<&str>) -> Result<T, String> {
+    let word = word.ok_or("missing number")?;
+    word.parse().map_err(|_| format!("`{}` is not a number", word))
+}
+
+/// `[3|5]`
+fn parse_tile(word: Option<&str>) -> Result<Tile, String> {
+    let word = word.ok_or("missing tile")?;
+    let pips = word
+        .strip_prefix('[')
+        .and_then(|word| word.strip_suffix(']'))
+        .and_then(|word| word.split_once('|'));
+    match pips {
+        Some((left, right)) => Ok(Tile::new(parse_number(Some(left))?, parse_number(Some(right))?)),
+        None => Err(format!("`{}` is not a tile", word)),
+    }
+}
+
+fn parse_tiles<'a, const N: usize>(
+    words: impl Iterator<Item = &'a str>,
+) -> Result<ArrayVec<Tile, N>, String> {
+    let mut tiles = ArrayVec::new();
+    for word in words {
+        tiles
+            .try_push(parse_tile(Some(word))?)
+            .map_err(|_| "too many tiles".to_string())?;
+    }
+    Ok(tiles)
+}
+
+impl FromStr for GameRecord {
+    type Err = ParseRecordError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut record = GameRecord::new(Position::new(
+            Vec::new(),
+            Boneyard { tiles: ArrayVec::new() },
+            Snake::new(),
+            0,
+        ));
+
+        for (index, line) in text.lines().enumerate() {
+            let error = |message: String| ParseRecordError {
+                line: index + 1,
+                message,
+            };
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+
+            match keyword {
+                "seed" => record.seed = Some(parse_number(words.next()).map_err(error)?),
+                "mode" => {
+                    record.start.game_mode = match words.next() {
+                        Some("draw") => GameMode::Draw,
+                        Some("block") => GameMode::Block,
+                        _ => return Err(error("the mode is either draw or block".to_string())),
+                    }
+                }
+                "partnership" => record.start.partnership = true,
+                "hand" => {
+                    let seat: usize = parse_number(words.next()).map_err(error)?;
+                    if seat != record.start.hands.len() || seat >= MAX_PLAYERS {
+                        return Err(error(format!("expected the hand of seat {}", record.start.hands.len())));
+                    }
+                    let tiles = parse_tiles(words).map_err(error)?;
+                    record.start.hands.push(Hand { tiles });
+                }
+                "boneyard" => record.start.boneyard.tiles = parse_tiles(words).map_err(error)?,
+                "snake" => record.start.snake.tiles = parse_tiles(words).map_err(error)?,
+                "turn" => record.start.current_player = parse_number(words.next()).map_err(error)?,
+                "draw" => {
+                    let seat = parse_number(words.next()).map_err(error)?;
+                    let tile = parse_tile(words.next()).map_err(error)?;
+                    record.push(Event::Draw { seat, tile });
+                }
+                "play" => {
+                    let seat = parse_number(words.next()).map_err(error)?;
+                    let tile = parse_tile(words.next()).map_err(error)?;
+                    let side = match words.next() {
+                        Some("left") => Side::Left,
+                        Some("right") => Side::Right,
+                        _ => return Err(error("the side is either left or right".to_string())),
+                    };
+                    record.push(Event::Play {
+                        seat,
+                        mv: Move::new(tile, side),
+                    });
+                }
+                "pass" => record.push(Event::Pass {
+                    seat: parse_number(words.next()).map_err(error)?,
+                }),
+                "result" => {
+                    let winner = match words.next() {
+                        Some("seat") => Some(Winner::Seat(parse_number(words.next()).map_err(error)?)),
+                        Some("team") => Some(Winner::Team(parse_number(words.next()).map_err(error)?)),
+                        Some("draw") => None,
+                        _ => return Err(error("the result is seat, team or draw".to_string())),
+                    };
+                    if words.next() != Some("scores") {
+                        return Err(error("expected the scores".to_string()));
+                    }
+                    let mut scores = ArrayVec::new();
+                    for word in words {
+                        let score = parse_number(Some(word)).map_err(error)?;
+                        scores.try_push(score).map_err(|_| error("too many scores".to_string()))?;
+                    }
+                    record.result = Some(GameState::Finished { winner, scores });
+                }
+                _ => return Err(error(format!("unknown entry `{}`", keyword))),
+            }
+        }
+
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, EventError, GameRecord, ReplayError};
+    use crate::{
+        first_possible_tile_ai_player::FirstPossibleTileAIPlayer,
+        game::{Game, GameMode, GameState},
+        greedy_ai_player::GreedyAIPlayer,
+        player::Player,
+        tile::Tile,
+    };
+
+    fn played_game(seed: u64, game_mode: GameMode) -> Game {
+        let players: Vec<Box<dyn Player>> = vec![
+            Box::new(GreedyAIPlayer),
+            Box::new(FirstPossibleTileAIPlayer),
+            Box::new(GreedyAIPlayer),
+        ];
+        let mut game = Game::from_seed(seed, players).game_mode(game_mode);
+        loop {
+            let (next, state) = game.play();
+            game = next;
+            if let GameState::Finished { .. } = state {
+                return game;
+            }
+        }
+    }
+
+    #[test]
+    fn the_notation_reads_back_the_same_record() {
+        for seed in 0..20 {
+            let game = played_game(seed, GameMode::Draw);
+            let text = game.record().to_string();
+
+            let record: GameRecord = text.parse().unwrap();
+            assert_eq!(&record, game.record());
+            assert!(record.result.is_some());
+            assert_eq!(record.seed, Some(seed));
+        }
+    }
+
+    #[test]
+    fn a_replay_steps_through_the_same_game() {
+        for seed in 0..20 {
+            let game = played_game(seed, GameMode::Draw);
+            let record: GameRecord = game.record().to_string().parse().unwrap();
+
+            let mut replay = record.replay().unwrap();
+            let mut steps = 0;
+            loop {
+                let (next, state) = replay.play();
+                replay = next;
+                steps += 1;
+                if let GameState::Finished { .. } = state {
+                    break;
+                }
+            }
+
+            assert_eq!(replay.record(), game.record());
+            assert_eq!(replay.snake(), game.snake());
+            let plays = record
+                .events
+                .iter()
+                .filter(|event| !matches!(event, Event::Draw { .. }))
+                .count();
+            // a blocked game takes one more step to notice
+            assert!(steps == plays || steps == plays + 1);
+        }
+    }
+
+    #[test]
+    fn a_replay_checks_every_event_against_the_rules() {
+        let record = played_game(3, GameMode::Draw).record().clone();
+        let event_error = |record: &GameRecord| match record.replay() {
+            Err(ReplayError::Event { reason, .. }) => reason,
+            other => panic!("{:?}", other.map(|_| ())),
+        };
+
+        let mut out_of_turn = record.clone();
+        out_of_turn.events.swap(0, 1);
+        assert!(matches!(event_error(&out_of_turn), EventError::OutOfTurn(_)));
+
+        let mut not_held = record.clone();
+        let Event::Play { mv, .. } = &mut not_held.events[0] else { panic!() };
+        mv.tile = Tile::new(6, 7);
+        assert_eq!(event_error(&not_held), EventError::NotHeld);
+
+        let mut other_seat = record.clone();
+        other_seat.events[0] = Event::Pass { seat: 9 };
+        assert_eq!(event_error(&other_seat), EventError::OutOfTurn(0));
+
+        let mut drawn = record.clone();
+        let Some(Event::Draw { tile, .. }) = drawn.events.iter_mut().find(|event| matches!(event, Event::Draw { .. })) else {
+            panic!("nobody drew")
+        };
+        *tile = Tile::new(6, 7);
+        assert!(matches!(event_error(&drawn), EventError::OtherTile(_)));
+
+        let mut longer = record.clone();
+        longer.events.push(*record.events.last().unwrap());
+        assert_eq!(event_error(&longer), EventError::GameOver);
+
+        let mut shorter = record.clone();
+        shorter.events.pop();
+        assert!(matches!(shorter.replay(), Err(ReplayError::Result { .. })));
+
+        let mut result = record.clone();
+        if let Some(GameState::Finished { scores, .. }) = &mut result.result {
+            scores[0] += 1;
+        }
+        assert!(matches!(result.replay(), Err(ReplayError::Result { .. })));
+    }
+
+    #[test]
+    fn a_record_without_a_result_replays_as_far_as_it_goes() {
+        let mut record = played_game(4, GameMode::Draw).record().clone();
+        // stop where a seat was about to play, like a game quit on that turn
+        let last_play = record.events.iter().rposition(|event| matches!(event, Event::Play { .. })).unwrap();
+        record.events.truncate(last_play);
+        record.result = None;
+
+        let mut replay = record.replay().unwrap();
+        while !replay.abandoned() {
+            let (next, state) = replay.play();
+            replay = next;
+            assert_eq!(state, GameState::Playing);
+        }
+        assert_eq!(replay.record(), &record);
+    }
+
+    #[test]
+    fn reports_the_line_of_a_bad_entry() {
+        let error = "mode block\nhand 0 [0|1]\nhand 1 [1|x]".parse::<GameRecord>().unwrap_err();
+        assert_eq!(error.line, 3);
+
+        let error = "mode block\nshuffle".parse::<GameRecord>().unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+}
@@ -0,0 +1,388 @@
+use std::{fmt, sync::Arc, thread, time::Duration};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    game::{Game, GameMode, GameState},
+    player::Player,
+    position::Position,
+    solver::Solver,
+    statistics::{elo_difference, Interval, Sprt, Verdict},
+    tile::MAX_PIP,
+};
+
+/// Results each thread plays between checks of the SPRT.
+const ROUND: u64 = 1000;
+
+/// Makes a fresh player for every game, given the game's seed so players
+/// with their own randomness can be made repeatable too.
+pub type PlayerFactory = Arc<dyn Fn(u64) -> Box<dyn Player> + Send + Sync>;
+
+/// How the first player did against the second. A result is a single
+/// game, or both games of a deal in a duplicate match.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Report {
+    pub games: u64,
+    pub duplicate: bool,
+    pub wins: u64,
+    /// blocked games with a tied lowest hand, or deals where both games
+    /// came out even
+    pub draws: u64,
+    pub losses: u64,
+    /// the points the first player won less those the second won
+    pub margin: i64,
+    /// the same per result, squared and summed
+    pub margin_squares: u64,
+    /// the log-likelihood ratio of the SPRT, when there is one
+    pub llr: Option<f64>,
+    /// set when the SPRT stopped the games early
+    pub verdict: Option<Verdict>,
+    pub elapsed: Duration,
+}
+
+impl Report {
+    pub fn results(&self) -> u64 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, positive when the first player is ahead.
+    pub fn average_margin(&self) -> f64 {
+        self.margin as f64 / self.games.max(1) as f64
+    }
+
+    /// Of the points per game, going by the spread of the results.
+    pub fn margin_interval(&self) -> Interval {
+        let results = self.results().max(1) as f64;
+        let per_result = (self.games as f64 / results).max(1.0);
+        let mean = self.margin as f64 / results;
+        let variance = (self.margin_squares as f64 / results - mean * mean).max(0.0);
+        Interval::normal(mean, (variance / results).sqrt()).map(|margin| margin / per_result)
+    }
+
+    /// Wins and half the draws, as a share of the results.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.results().max(1) as f64
+    }
+
+    /// The variance of a single result's score, 1 for a win, 0.5 for a
+    /// draw and 0 for a loss.
+    pub fn score_variance(&self) -> f64 {
+        let score = self.score();
+        (self.wins as f64 + self.draws as f64 / 4.0) / self.results().max(1) as f64 - score * score
+    }
+
+    pub fn score_interval(&self) -> Interval {
+        Interval::normal(self.score(), (self.score_variance() / self.results().max(1) as f64).sqrt())
+    }
+
+    /// How much stronger the first player is, from `score_interval`.
+    pub fn elo_interval(&self) -> Interval {
+        self.score_interval().map(|score| elo_difference(score.clamp(0.0, 1.0)))
+    }
+
+    pub fn games_per_second(&self) -> f64 {
+        self.games as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// Adds up the games of both, the rest is left alone.
+    fn merge(&mut self, other: &Report) {
+        self.games += other.games;
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+        self.margin += other.margin;
+        self.margin_squares += other.margin_squares;
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let share = |count: u64| 100.0 * count as f64 / self.results().max(1) as f64;
+        let (score, elo, margin) = (self.score_interval(), self.elo_interval(), self.margin_interval());
+        write!(f, "{} games", self.games)?;
+        if self.duplicate {
+            write!(f, " over {} duplicate deals", self.results())?;
+        }
+        writeln!(f, " in {:.1?}, {:.0} per second", self.elapsed, self.games_per_second())?;
+        writeln!(f, "won   {:>10} ({:5.1}%)", self.wins, share(self.wins))?;
+        writeln!(f, "drawn {:>10} ({:5.1}%)", self.draws, share(self.draws))?;
+        writeln!(f, "lost  {:>10} ({:5.1}%)", self.losses, share(self.losses))?;
+        writeln!(f, "score {:.1}% ± {:.1}%, elo {:+.1} [{:+.1}, {:+.1}]", 100.0 * score.estimate, 100.0 * score.error(), elo.estimate, elo.low, elo.high)?;
+        write!(f, "average margin {:+.2} ± {:.2} points", margin.estimate, margin.error())?;
+        if let Some(llr) = self.llr {
+            match self.verdict {
+                Some(verdict) => write!(f, "\nsprt llr {:+.2}, accepted {}", llr, verdict)?,
+                None => write!(f, "\nsprt llr {:+.2}, undecided", llr)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The RNG and seat order of one thread, carried from round to round.
+struct Worker {
+    rng: StdRng,
+    played: u64,
+}
+
+/// Plays two players against each other over many games on several
+/// threads. Every thread deals from its own RNG seeded from the
+/// simulation's seed, so a run with the same seed and thread count always
+/// gives the same result. The threads play in rounds, after each of which
+/// an SPRT may end the match.
+pub struct Simulation {
+    first: PlayerFactory,
+    second: PlayerFactory,
+    games: u64,
+    threads: usize,
+    seed: u64,
+    game_mode: GameMode,
+    sprt: Option<Sprt>,
+    duplicate: bool,
+    max_pip: u8,
+    hand_size: usize,
+}
+
+impl Simulation {
+    pub fn new(first: PlayerFactory, second: PlayerFactory) -> Self {
+        Simulation {
+            first,
+            second,
+            games: 1000,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+            seed: 0,
+            game_mode: GameMode::Block,
+            sprt: None,
+            duplicate: false,
+            max_pip: MAX_PIP,
+            hand_size: Game::hand_size(2),
+        }
+    }
+
+    pub fn games(mut self, games: u64) -> Self {
+        self.games = games;
+        self
+    }
+
+    /// Defaults to one per core.
+    pub fn threads(mut self, threads: usize) -> Self {
+        assert!(threads > 0, "at least one thread is needed");
+        self.threads = threads;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn game_mode(mut self, game_mode: GameMode) -> Self {
+        self.game_mode = game_mode;
+        self
+    }
+
+    /// Stops as soon as `sprt` accepts either hypothesis, `games` being
+    /// the most to play.
+    pub fn sprt(mut self, sprt: Sprt) -> Self {
+        self.sprt = Some(sprt);
+        self
+    }
+
+    /// Plays every deal twice, the players swapping hands and seats in
+    /// the second game, and scores the deal by the two games together. The
+    /// luck of the deal cancels out, so far fewer games tell the players
+    /// apart. An odd number of games gets one more.
+    pub fn duplicate(mut self, duplicate: bool) -> Self {
+        self.duplicate = duplicate;
+        self
+    }
+
+    /// Deals `hand_size` tiles a seat from the double-`max_pip` set rather
+    /// than the full one, see `Position::deal_set`.
+    pub fn set(mut self, max_pip: u8, hand_size: usize) -> Self {
+        assert!(max_pip <= MAX_PIP, "sets go up to double-{}", MAX_PIP);
+        let tiles = (max_pip as usize + 1) * (max_pip as usize + 2) / 2;
+        assert!(hand_size > 0 && 2 * hand_size <= tiles, "can't deal {} tiles each from {}", hand_size, tiles);
+        self.max_pip = max_pip;
+        self.hand_size = hand_size;
+        self
+    }
+
+    pub fn run(&self) -> Report {
+        let started = quanta::Instant::now();
+        let mut workers: Vec<Worker> = (0..self.threads)
+            .map(|thread| Worker {
+                rng: StdRng::seed_from_u64(self.seed.wrapping_add(thread as u64)),
+                played: 0,
+            })
+            .collect();
+
+        let results = if self.duplicate { self.games.div_ceil(2) } else { self.games };
+        let mut report = Report {
+            duplicate: self.duplicate,
+            ..Report::default()
+        };
+        while report.results() < results && report.verdict.is_none() {
+            let round = (results - report.results()).min(ROUND * self.threads as u64);
+            let reports: Vec<Report> = thread::scope(|scope| {
+                let handles: Vec<_> = workers
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(thread, worker)| {
+                        // the first threads take one more when the games don't split evenly
+                        let games = round / self.threads as u64 + u64::from((thread as u64) < round % self.threads as u64);
+                        scope.spawn(move || self.play(worker, games))
+                    })
+                    .collect();
+                handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+            });
+            for thread in reports.iter() {
+                report.merge(thread);
+            }
+
+            if let Some(sprt) = self.sprt {
+                let llr = sprt.llr(report.results(), report.score(), report.score_variance());
+                report.llr = Some(llr);
+                report.verdict = sprt.verdict(llr);
+            }
+        }
+        report.elapsed = started.elapsed();
+        report
+    }
+
+    /// Results of one thread. Outside duplicate matches the players swap
+    /// seats every game.
+    fn play(&self, worker: &mut Worker, results: u64) -> Report {
+        let mut report = Report::default();
+        for _ in 0..results {
+            let seed = worker.rng.gen();
+            let position = Position::deal_set(&mut StdRng::seed_from_u64(seed), 2, self.max_pip, self.hand_size);
+            let margin = if self.duplicate {
+                report.games += 2;
+                self.margin(Game::from_position(position.clone(), self.seat(seed, 0)).unwrap(), 0)
+                    + self.margin(Game::from_position(position, self.seat(seed, 1)).unwrap(), 1)
+            } else {
+                let first_seat = (worker.played % 2) as usize;
+                worker.played += 1;
+                report.games += 1;
+                self.margin(Game::from_position(position, self.seat(seed, first_seat)).unwrap(), first_seat)
+            };
+
+            report.margin += margin;
+            report.margin_squares += (margin * margin) as u64;
+            match margin {
+                0 => report.draws += 1,
+                margin if margin > 0 => report.wins += 1,
+                _ => report.losses += 1,
+            }
+        }
+        report
+    }
+
+    /// Both players for a game, the first one in `first_seat`.
+    fn seat(&self, seed: u64, first_seat: usize) -> Vec<Box<dyn Player>> {
+        let mut players = vec![(self.second)(seed)];
+        players.insert(first_seat, (self.first)(seed));
+        players
+    }
+
+    /// Plays `game` out and returns the points to the first player.
+    fn margin(&self, game: Game, first_seat: usize) -> i64 {
+        let mut game = game.game_mode(self.game_mode);
+        let state = loop {
+            let (next, state) = game.play();
+            game = next;
+            if let GameState::Finished { .. } = state {
+                break state;
+            }
+        };
+        let value = Solver::value(&state) as i64;
+        if first_seat == 0 { value } else { -value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{PlayerFactory, Simulation};
+    use crate::{
+        first_possible_tile_ai_player::FirstPossibleTileAIPlayer,
+        greedy_ai_player::GreedyAIPlayer,
+        statistics::{Sprt, Verdict},
+    };
+
+    fn simulation() -> Simulation {
+        let greedy: PlayerFactory = Arc::new(|_| Box::new(GreedyAIPlayer));
+        let first_possible: PlayerFactory = Arc::new(|_| Box::new(FirstPossibleTileAIPlayer));
+        Simulation::new(greedy, first_possible).seed(7)
+    }
+
+    #[test]
+    fn every_game_is_counted_once() {
+        let report = simulation().games(101).threads(4).run();
+        assert_eq!(report.games, 101);
+        assert_eq!(report.wins + report.draws + report.losses, 101);
+    }
+
+    #[test]
+    fn the_same_seed_and_threads_give_the_same_result() {
+        let first = simulation().games(200).threads(3).run();
+        let second = simulation().games(200).threads(3).run();
+        assert_eq!((first.wins, first.draws, first.losses, first.margin), (second.wins, second.draws, second.losses, second.margin));
+
+        let other = simulation().seed(8).games(200).threads(3).run();
+        assert_ne!((first.wins, first.margin), (other.wins, other.margin));
+    }
+
+    #[test]
+    fn plays_out_deals_from_a_smaller_set() {
+        let report = simulation().set(3, 5).games(101).threads(2).run();
+        assert_eq!(report.results(), 101);
+        // there are only 30 pips in the whole double-three set
+        assert!(report.margin.abs() <= 101 * 30);
+    }
+
+    #[test]
+    fn the_greedy_player_beats_the_first_possible_tile() {
+        let report = simulation().games(400).threads(2).run();
+        assert!(report.wins > report.losses, "{}", report);
+        assert!(report.average_margin() > 0.0);
+    }
+
+    #[test]
+    fn intervals_cover_the_estimates() {
+        let report = simulation().games(400).threads(2).run();
+        let score = report.score_interval();
+        assert!(score.low < report.score() && report.score() < score.high);
+        assert!(score.error() > 0.0 && score.error() < 0.1);
+        let margin = report.margin_interval();
+        assert!(margin.contains(report.average_margin()) && margin.error() > 0.0);
+        assert!(report.elo_interval().low > 0.0, "{}", report);
+    }
+
+    #[test]
+    fn the_sprt_stops_early_once_one_player_is_clearly_stronger() {
+        let report = simulation().games(1_000_000).threads(2).sprt(Sprt::new(0.0, 20.0)).run();
+        assert_eq!(report.verdict, Some(Verdict::H1), "{}", report);
+        assert!(report.games < 1_000_000);
+    }
+
+    #[test]
+    fn duplicate_deals_cancel_out_between_equal_players() {
+        let greedy: PlayerFactory = Arc::new(|_| Box::new(GreedyAIPlayer));
+        let report = Simulation::new(greedy.clone(), greedy).games(101).threads(2).duplicate(true).run();
+        assert_eq!(report.games, 102);
+        assert_eq!(report.draws, 51);
+        assert_eq!(report.margin, 0);
+    }
+
+    #[test]
+    fn duplicate_deals_narrow_the_intervals() {
+        let single = simulation().games(2000).threads(2).run();
+        let duplicate = simulation().games(2000).threads(2).duplicate(true).run();
+        assert_eq!(duplicate.results(), 1000);
+        assert!(duplicate.margin_interval().error() < single.margin_interval().error(), "{}\n{}", single, duplicate);
+        assert!(duplicate.margin_interval().low > 0.0);
+    }
+}
@@ -1,14 +1,30 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
 use arrayvec::ArrayVec;
 
-use crate::tile::Tile;
+use crate::{
+    game_move::{Move, Side},
+    tile::Tile,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Snake {
     pub tiles: ArrayVec<Tile, 27>,
 }
 
+/// The tile has no pip matching the end of the snake it was played on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IllegalMove(pub Move);
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} does not fit the {:?} end", self.0.tile, self.0.side)
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
 impl Snake {
     pub fn new() -> Snake {
         Snake { tiles: ArrayVec::new() }
@@ -22,37 +38,60 @@ impl Snake {
         self.tiles.last().map(|tile| tile.right)
     }
 
+    pub fn end(&self, side: Side) -> Option<u8> {
+        match side {
+            Side::Left => self.left(),
+            Side::Right => self.right(),
+        }
+    }
+
     pub fn is_playable(&self, tile: Tile) -> bool {
-        self.left().is_none()
-            || self.left() == Some(tile.left)
-            || self.left() == Some(tile.right)
-            || self.right() == Some(tile.left)
-            || self.right() == Some(tile.right)
+        self.fits(tile, Side::Left) || self.fits(tile, Side::Right)
+    }
+
+    /// whether the tile can be placed on that end of the snake
+    pub fn fits(&self, tile: Tile, side: Side) -> bool {
+        match self.end(side) {
+            None => true,
+            Some(pips) => tile.left == pips || tile.right == pips,
+        }
     }
 
     pub fn is_empty(&self) -> bool {
         self.tiles.is_empty()
     }
 
-    /// the tile should be playeble
-    /// the tile is playable if it can be placed on the left or right side of the snake
-    pub fn add(&mut self, tile: Tile) {
+    /// places the tile on the given end, turning it around if needed
+    pub fn play(&mut self, tile: Tile, side: Side) -> Result<(), IllegalMove> {
         if self.tiles.is_empty() {
             self.tiles.push(tile);
-        } else if self.left() == Some(tile.right) {
+        } else if side == Side::Left && self.left() == Some(tile.right) {
             self.tiles.insert(0, tile);
-        } else if self.left() == Some(tile.left) {
+        } else if side == Side::Left && self.left() == Some(tile.left) {
             self.tiles.insert(0, tile.flip());
-        } else if self.right() == Some(tile.left) {
+        } else if side == Side::Right && self.right() == Some(tile.left) {
             self.tiles.push(tile);
-        } else if self.right() == Some(tile.right) {
+        } else if side == Side::Right && self.right() == Some(tile.right) {
             self.tiles.push(tile.flip());
+        } else {
+            return Err(IllegalMove(Move::new(tile, side)));
         }
 
         assert!(self.is_valid());
+        Ok(())
     }
 
-    fn is_valid(&self) -> bool {
+    /// undoes `play`, returning the tile as it lies on the snake
+    pub fn take_back(&mut self, side: Side) -> Option<Tile> {
+        match side {
+            Side::Left if !self.tiles.is_empty() => Some(self.tiles.remove(0)),
+            Side::Left => None,
+            Side::Right => self.tiles.pop(),
+        }
+    }
+
+    /// neighbouring tiles share a pip
+    pub fn is_valid(&self) -> bool {
         if self.tiles.is_empty() {
             return true;
         }
@@ -67,3 +106,88 @@ impl Snake {
         true
     }
 }
+
+impl Default for Snake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IllegalMove, Snake};
+    use crate::{
+        game_move::{Move, Side},
+        tile::Tile,
+    };
+
+    fn snake(tiles: &[(u8, u8)]) -> Snake {
+        let mut snake = Snake::new();
+        for &(left, right) in tiles {
+            snake.play(Tile::new(left, right), Side::Right).unwrap();
+        }
+        snake
+    }
+
+    #[test]
+    fn a_tile_fitting_both_ends_goes_where_it_is_played() {
+        let tile = Tile::new(3, 5);
+
+        let mut left = snake(&[(3, 5)]);
+        left.play(tile, Side::Left).unwrap();
+        assert_eq!(left.tiles.as_slice(), &[Tile::new(5, 3), Tile::new(3, 5)]);
+
+        let mut right = snake(&[(3, 5)]);
+        right.play(tile, Side::Right).unwrap();
+        assert_eq!(right.tiles.as_slice(), &[Tile::new(3, 5), Tile::new(5, 3)]);
+    }
+
+    #[test]
+    fn tiles_are_turned_to_match_the_end() {
+        let mut snake = snake(&[(2, 4)]);
+        snake.play(Tile::new(4, 6), Side::Right).unwrap();
+        snake.play(Tile::new(1, 2), Side::Left).unwrap();
+        snake.play(Tile::new(0, 6), Side::Right).unwrap();
+        snake.play(Tile::new(1, 3), Side::Left).unwrap();
+
+        assert_eq!(snake.left(), Some(3));
+        assert_eq!(snake.right(), Some(0));
+        assert_eq!(snake.tiles.len(), 5);
+    }
+
+    #[test]
+    fn a_tile_only_fitting_the_other_end_is_illegal() {
+        let mut snake = snake(&[(3, 5)]);
+        let tile = Tile::new(5, 6);
+
+        assert!(snake.fits(tile, Side::Right));
+        assert!(!snake.fits(tile, Side::Left));
+        assert_eq!(
+            snake.play(tile, Side::Left),
+            Err(IllegalMove(Move::new(tile, Side::Left)))
+        );
+        assert_eq!(snake.tiles.as_slice(), &[Tile::new(3, 5)]);
+    }
+
+    #[test]
+    fn any_tile_starts_the_snake() {
+        let mut snake = Snake::new();
+        assert!(snake.fits(Tile::new(6, 6), Side::Left));
+        snake.play(Tile::new(6, 6), Side::Left).unwrap();
+        assert_eq!(snake.left(), Some(6));
+        assert_eq!(snake.right(), Some(6));
+    }
+
+    #[test]
+    fn taking_back_a_tile_restores_the_ends() {
+        let mut snake = snake(&[(3, 5)]);
+        snake.play(Tile::new(6, 3), Side::Left).unwrap();
+        snake.play(Tile::new(5, 1), Side::Right).unwrap();
+
+        assert_eq!(snake.take_back(Side::Left), Some(Tile::new(6, 3)));
+        assert_eq!(snake.take_back(Side::Right), Some(Tile::new(5, 1)));
+        assert_eq!(snake, self::snake(&[(3, 5)]));
+        assert_eq!(snake.take_back(Side::Left), Some(Tile::new(3, 5)));
+        assert_eq!(snake.take_back(Side::Right), None);
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+
+use crate::{
+    game::{GameMode, GameState, Winner},
+    game_move::Action,
+    hand::Hand,
+    position::Position,
+};
+
+/// The exact result of a solved position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Solution {
+    /// the points the player to move wins with best play on both sides,
+    /// negative when they lose them
+    pub value: i32,
+    /// `None` once the game is over
+    pub best: Option<Action>,
+}
+
+/// Everything the rest of a two player game depends on once nothing is drawn
+/// anymore. Which tiles lie on the snake doesn't matter beyond its ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Key {
+    /// a bit per `Tile::index`
+    hands: [u32; 2],
+    ends: Option<(u8, u8)>,
+    current_player: u8,
+    blocked_counter: u8,
+}
+
+impl Key {
+    fn new(position: &Position) -> Key {
+        let bits = |hand: &Hand| hand.tiles.iter().fold(0, |bits, tile| bits | 1 << tile.index());
+        Key {
+            hands: [bits(&position.hands[0]), bits(&position.hands[1])],
+            ends: position.snake.left().zip(position.snake.right()),
+            current_player: position.current_player as u8,
+            blocked_counter: position.blocked_counter,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    /// the search failed high, the value is at least this
+    Lower,
+    /// the search failed low, the value is at most this
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    /// for seat 0
+    value: i32,
+    bound: Bound,
+    best: Option<Action>,
+}
+
+/// Alpha-beta search to the end of two player games in which every tile is
+/// known and nothing is left to draw. Solved positions are kept across calls,
+/// so solving the next turn of the same game is mostly lookups.
+#[derive(Default)]
+pub struct Solver {
+    table: HashMap<Key, Entry>,
+    /// positions searched so far
+    pub nodes: u64,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver::default()
+    }
+
+    /// Two players without anything left to draw; hands and boneyard must be
+    /// the real ones.
+    pub fn can_solve(position: &Position) -> bool {
+        position.hands.len() == 2
+            && !position.partnership
+            && (position.game_mode == GameMode::Block || position.boneyard.is_empty())
+    }
+
+    /// `None` when the position isn't one `can_solve` takes.
+    pub fn solve(&mut self, position: &Position) -> Option<Solution> {
+        if !Self::can_solve(position) {
+            return None;
+        }
+
+        let mut position = position.clone();
+        let value = self.search(&mut position, i32::MIN, i32::MAX);
+        let best = self.table.get(&Key::new(&position)).and_then(|entry| entry.best);
+        let value = if position.current_player == 0 { value } else { -value };
+        Some(Solution { value, best })
+    }
+
+    /// Forgets every solved position.
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    /// What the finished game is worth to seat 0 of two: the points won,
+    /// negative when seat 1 won them.
+    pub fn value(state: &GameState) -> i32 {
+        match state {
+            GameState::Finished {
+                winner: Some(Winner::Seat(seat)),
+                scores,
+            } if *seat == 0 => scores[0],
+            GameState::Finished {
+                winner: Some(Winner::Seat(seat)),
+                scores,
+            } => -scores[*seat],
+            _ => 0,
+        }
+    }
+
+    /// Minimax for seat 0, who maximizes.
+    fn search(&mut self, position: &mut Position, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        if let state @ GameState::Finished { .. } = position.state() {
+            return Self::value(&state);
+        }
+
+        let key = Key::new(position);
+        let (start_alpha, start_beta) = (alpha, beta);
+        let mut actions = position.legal_actions();
+        if let Some(entry) = self.table.get(&key) {
+            match entry.bound {
+                Bound::Exact => return entry.value,
+                Bound::Lower => alpha = alpha.max(entry.value),
+                Bound::Upper => beta = beta.min(entry.value),
+            }
+            if alpha >= beta {
+                return entry.value;
+            }
+            // the best action last time is the most likely to cut off again
+            if let Some(index) = actions.iter().position(|action| Some(*action) == entry.best) {
+                actions.swap(0, index);
+            }
+        }
+
+        let maximizing = position.current_player == 0;
+        let mut best_value = if maximizing { i32::MIN } else { i32::MAX };
+        let mut best = None;
+        for action in actions {
+            position.apply(action);
+            let value = self.search(position, alpha, beta);
+            position.undo();
+
+            if maximizing && value > best_value || !maximizing && value < best_value {
+                best_value = value;
+                best = Some(action);
+            }
+            if maximizing {
+                alpha = alpha.max(value);
+            } else {
+                beta = beta.min(value);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if best_value <= start_alpha {
+            Bound::Upper
+        } else if best_value >= start_beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.table.insert(
+            key,
+            Entry {
+                value: best_value,
+                bound,
+                best,
+            },
+        );
+        best_value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    use super::Solver;
+    use crate::{
+        boneyard::Boneyard,
+        game::{GameMode, GameState},
+        game_move::{Action, Side},
+        hand::Hand,
+        position::{
+            fixtures::{go_out_first, going_out_first},
+            Position,
+        },
+        snake::Snake,
+        tile::Tile,
+    };
+
+    fn position(hands: [&[Tile]; 2], snake: Snake) -> Position {
+        let hands = hands.map(|tiles| Hand {
+            tiles: tiles.iter().copied().collect(),
+        });
+        Position::new(hands, Boneyard { tiles: Default::default() }, snake, 0)
+    }
+
+    /// Plain minimax for the player to move, nothing cut off or remembered.
+    fn minimax(position: &mut Position) -> i32 {
+        if let state @ GameState::Finished { .. } = position.state() {
+            let value = Solver::value(&state);
+            return if position.current_player == 0 { value } else { -value };
+        }
+        let seat = position.current_player;
+        let mut best = i32::MIN;
+        for action in position.legal_actions() {
+            position.apply(action);
+            let value = minimax(position);
+            let value = if position.current_player == seat { value } else { -value };
+            position.undo();
+            best = best.max(value);
+        }
+        best
+    }
+
+    #[test]
+    fn finds_the_only_way_to_go_out_first() {
+        let solution = Solver::new().solve(&go_out_first()).unwrap();
+
+        // seat 0 goes out while seat 1 still holds all 12 pips
+        assert_eq!(solution.best, Some(Action::Play(going_out_first())));
+        assert_eq!(solution.value, 12);
+    }
+
+    #[test]
+    fn agrees_with_plain_minimax() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut solver = Solver::new();
+        for _ in 0..30 {
+            let mut tiles = Boneyard::new().tiles;
+            tiles.shuffle(&mut rng);
+            let mut snake = Snake::new();
+            snake.play(tiles[0], Side::Right).unwrap();
+            let mut position = position([&tiles[1..6], &tiles[6..11]], snake);
+            position.current_player = 1;
+
+            let solution = solver.solve(&position).unwrap();
+            assert_eq!(solution.value, minimax(&mut position));
+        }
+    }
+
+    #[test]
+    fn only_solves_two_players_with_nothing_to_draw() {
+        let mut position = position([&[Tile::new(0, 1)], &[Tile::new(2, 3)]], Snake::new());
+        position.boneyard.tiles.push(Tile::new(6, 6));
+        position.game_mode = GameMode::Draw;
+        assert_eq!(Solver::new().solve(&position), None);
+
+        position.game_mode = GameMode::Block;
+        assert!(Solver::new().solve(&position).is_some());
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+
+/// How many standard errors a 95% confidence interval spans either side.
+pub const Z_95: f64 = 1.959964;
+
+/// The share of the points a player scoring `elo` above its opponent is
+/// expected to take.
+pub fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// The inverse of `expected_score`, infinite for a score of 0 or 1.
+pub fn elo_difference(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// An estimate with its 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub estimate: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Interval {
+    /// From a sample mean and the standard error of that mean.
+    pub fn normal(mean: f64, standard_error: f64) -> Interval {
+        Interval {
+            estimate: mean,
+            low: mean - Z_95 * standard_error,
+            high: mean + Z_95 * standard_error,
+        }
+    }
+
+    /// Half the width of the interval.
+    pub fn error(&self) -> f64 {
+        (self.high - self.low) / 2.0
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.low <= value && value <= self.high
+    }
+
+    /// Applies a rising function to every bound.
+    pub fn map(&self, f: impl Fn(f64) -> f64) -> Interval {
+        Interval {
+            estimate: f(self.estimate),
+            low: f(self.low),
+            high: f(self.high),
+        }
+    }
+}
+
+/// Which hypothesis a `Sprt` accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// the first player is no more than `elo0` stronger
+    H0,
+    /// the first player is at least `elo1` stronger
+    H1,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::H0 => write!(f, "H0"),
+            Verdict::H1 => write!(f, "H1"),
+        }
+    }
+}
+
+/// A sequential probability ratio test between the first player being
+/// `elo0` or `elo1` stronger than the second, checked as the games come
+/// in so a match can stop as soon as the answer is clear. Uses the normal
+/// approximation to the log-likelihood ratio of the game scores, draws
+/// counting half.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// the chance of accepting H1 when H0 holds
+    pub alpha: f64,
+    /// the chance of accepting H0 when H1 holds
+    pub beta: f64,
+}
+
+impl Sprt {
+    /// With 5% chances of either kind of error.
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        assert!(elo0 < elo1, "elo1 has to be the stronger hypothesis");
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    pub fn errors(mut self, alpha: f64, beta: f64) -> Self {
+        self.alpha = alpha;
+        self.beta = beta;
+        self
+    }
+
+    /// Where the log-likelihood ratio accepts H0 and H1.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    /// The log-likelihood ratio of H1 against H0 after `games` games,
+    /// scoring `score` on average with `variance` per game.
+    pub fn llr(&self, games: u64, score: f64, variance: f64) -> f64 {
+        if games == 0 || variance <= 0.0 {
+            return 0.0;
+        }
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+        games as f64 * (score1 - score0) * (2.0 * score - score0 - score1) / (2.0 * variance)
+    }
+
+    /// `None` until the ratio crosses one of the bounds.
+    pub fn verdict(&self, llr: f64) -> Option<Verdict> {
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            Some(Verdict::H0)
+        } else if llr >= upper {
+            Some(Verdict::H1)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{elo_difference, expected_score, Interval, Sprt, Verdict};
+
+    #[test]
+    fn elo_and_score_convert_both_ways() {
+        assert_eq!(expected_score(0.0), 0.5);
+        assert!((expected_score(400.0) - 10.0 / 11.0).abs() < 1e-12);
+        for elo in [-300.0, -20.0, 0.0, 5.0, 250.0] {
+            assert!((elo_difference(expected_score(elo)) - elo).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn a_normal_interval_spans_about_two_standard_errors() {
+        let interval = Interval::normal(3.0, 0.5);
+        assert!((interval.error() - 0.98).abs() < 0.01);
+        assert!(interval.contains(3.9) && !interval.contains(4.0));
+    }
+
+    #[test]
+    fn the_sprt_decides_once_the_evidence_is_strong() {
+        let sprt = Sprt::new(0.0, 10.0);
+        let (lower, upper) = sprt.bounds();
+        assert!((upper - 2.944).abs() < 1e-3 && (lower + 2.944).abs() < 1e-3);
+
+        // a 60% score with no draws
+        let variance = 0.6 - 0.6 * 0.6;
+        assert_eq!(sprt.verdict(sprt.llr(10, 0.6, variance)), None);
+        assert_eq!(sprt.verdict(sprt.llr(2000, 0.6, variance)), Some(Verdict::H1));
+        assert_eq!(sprt.verdict(sprt.llr(10000, 0.5, 0.25)), Some(Verdict::H0));
+    }
+}
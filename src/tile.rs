@@ -1,6 +1,33 @@
 use std::fmt;
 
+/// The highest pip of the full double-six set. Smaller sets stop earlier,
+/// see `Boneyard::with_max_pip`.
+pub const MAX_PIP: u8 = 6;
+
+/// Lying down, indexed by the left pip and then the right one.
+const HORIZONTAL_GLYPHS: [[char; 7]; 7] = [
+    ['🀱', '🀲', '🀳', '🀴', '🀵', '🀶', '🀷'],
+    ['🀸', '🀹', '🀺', '🀻', '🀼', '🀽', '🀾'],
+    ['🀿', '🁀', '🁁', '🁂', '🁃', '🁄', '🁅'],
+    ['🁆', '🁇', '🁈', '🁉', '🁊', '🁋', '🁌'],
+    ['🁍', '🁎', '🁏', '🁐', '🁑', '🁒', '🁓'],
+    ['🁔', '🁕', '🁖', '🁗', '🁘', '🁙', '🁚'],
+    ['🁛', '🁜', '🁝', '🁞', '🁟', '🁠', '🁡'],
+];
+
+/// Standing up, indexed by the top pip and then the bottom one.
+const VERTICAL_GLYPHS: [[char; 7]; 7] = [
+    ['🁣', '🁤', '🁥', '🁦', '🁧', '🁨', '🁩'],
+    ['🁪', '🁫', '🁬', '🁭', '🁮', '🁯', '🁰'],
+    ['🁱', '🁲', '🁳', '🁴', '🁵', '🁶', '🁷'],
+    ['🁸', '🁹', '🁺', '🁻', '🁼', '🁽', '🁾'],
+    ['🁿', '🂀', '🂁', '🂂', '🂃', '🂄', '🂅'],
+    ['🂆', '🂇', '🂈', '🂉', '🂊', '🂋', '🂌'],
+    ['🂍', '🂎', '🂏', '🂐', '🂑', '🂒', '🂓'],
+];
+
 #[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tile {
     pub left: u8,
     pub right: u8,
@@ -18,9 +45,35 @@ impl Tile {
         }
     }
 
+    /// the same tile with the smaller pip on the left
+    pub fn normalized(&self) -> Tile {
+        if self.left <= self.right {
+            *self
+        } else {
+            self.flip()
+        }
+    }
+
+    /// 0 for [0|0] up to 27 for [6|6], the same for both ways round
+    pub fn index(&self) -> usize {
+        let Tile { left, right } = self.normalized();
+        let (left, right) = (left as usize, right as usize);
+        7 * left - left * left.saturating_sub(1) / 2 + (right - left)
+    }
+
     pub fn score(&self) -> u8 {
         self.left + self.right
     }
+
+    /// The Unicode domino lying down, as on the snake.
+    pub fn horizontal_glyph(&self) -> char {
+        HORIZONTAL_GLYPHS[self.left as usize][self.right as usize]
+    }
+
+    /// The Unicode domino standing up, as in a hand.
+    pub fn vertical_glyph(&self) -> char {
+        VERTICAL_GLYPHS[self.left as usize][self.right as usize]
+    }
 }
 
 impl fmt::Debug for Tile {
@@ -28,3 +81,25 @@ impl fmt::Debug for Tile {
         write!(f, "[{}|{}]", self.left, self.right)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Tile;
+    use crate::boneyard::Boneyard;
+
+    #[test]
+    fn every_tile_has_its_own_index() {
+        for (index, tile) in Boneyard::new().tiles.iter().enumerate() {
+            assert_eq!(tile.index(), index);
+            assert_eq!(tile.flip().index(), index);
+        }
+    }
+
+    #[test]
+    fn glyphs_follow_the_unicode_order() {
+        assert_eq!(Tile::new(0, 0).horizontal_glyph(), '\u{1F031}');
+        assert_eq!(Tile::new(3, 5).horizontal_glyph() as u32, 0x1F031 + 7 * 3 + 5);
+        assert_eq!(Tile::new(6, 6).vertical_glyph(), '\u{1F093}');
+        assert_eq!(Tile::new(5, 3).vertical_glyph() as u32, 0x1F063 + 7 * 5 + 3);
+    }
+}
@@ -0,0 +1,324 @@
+use std::{fmt, sync::Arc};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    expectimax_ai_player::ExpectimaxAIPlayer,
+    first_possible_tile_ai_player::FirstPossibleTileAIPlayer,
+    game::GameMode,
+    greedy_ai_player::GreedyAIPlayer,
+    ismcts_ai_player::IsmctsAIPlayer,
+    mcts_ai_player::MctsAIPlayer,
+    observent_ai_player::ObserventAIPlayer,
+    simulation::{PlayerFactory, Report, Simulation},
+};
+
+/// The rating of an average entrant.
+const MEAN_RATING: f64 = 1500.0;
+/// Rounds of the rating fit, far more than it takes to settle.
+const FIT_ROUNDS: usize = 1000;
+
+/// A kind of player taking part in a tournament, under a name to show on
+/// the leaderboard.
+#[derive(Clone)]
+pub struct Entrant {
+    pub name: String,
+    pub factory: PlayerFactory,
+}
+
+impl Entrant {
+    pub fn new(name: impl Into<String>, factory: PlayerFactory) -> Self {
+        Entrant {
+            name: name.into(),
+            factory,
+        }
+    }
+}
+
+/// Every player that can be made without anything but a seed, with its
+/// default settings. New player types belong here.
+pub fn registered() -> Vec<Entrant> {
+    vec![
+        Entrant::new("first-possible", Arc::new(|_| Box::new(FirstPossibleTileAIPlayer))),
+        Entrant::new("greedy", Arc::new(|_| Box::new(GreedyAIPlayer))),
+        Entrant::new("observent", Arc::new(|_| Box::new(ObserventAIPlayer::default()))),
+        Entrant::new("expectimax", Arc::new(|_| Box::new(ExpectimaxAIPlayer::default()))),
+        Entrant::new("mcts", Arc::new(|seed| Box::new(MctsAIPlayer::default().seed(seed)))),
+        Entrant::new("ismcts", Arc::new(|seed| Box::new(IsmctsAIPlayer::default().seed(seed)))),
+    ]
+}
+
+/// The registered entrant called `name`.
+pub fn find(name: &str) -> Option<Entrant> {
+    registered().into_iter().find(|entrant| entrant.name == name)
+}
+
+/// Plays every pair of entrants against each other, each pairing a
+/// `Simulation` with the seats swapping every game, and rates them all.
+pub struct Tournament {
+    entrants: Vec<Entrant>,
+    games: u64,
+    threads: Option<usize>,
+    seed: u64,
+    game_mode: GameMode,
+    duplicate: bool,
+}
+
+impl Tournament {
+    pub fn new(entrants: Vec<Entrant>) -> Self {
+        assert!(entrants.len() >= 2, "a tournament needs two entrants");
+        Tournament {
+            entrants,
+            games: 100,
+            threads: None,
+            seed: 0,
+            game_mode: GameMode::Block,
+            duplicate: false,
+        }
+    }
+
+    /// How many games each pairing plays.
+    pub fn games(mut self, games: u64) -> Self {
+        self.games = games;
+        self
+    }
+
+    /// Defaults to one per core, like `Simulation`.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn game_mode(mut self, game_mode: GameMode) -> Self {
+        self.game_mode = game_mode;
+        self
+    }
+
+    /// Plays duplicate deals, see `Simulation::duplicate`.
+    pub fn duplicate(mut self, duplicate: bool) -> Self {
+        self.duplicate = duplicate;
+        self
+    }
+
+    pub fn run(&self) -> Standings {
+        self.run_with(|_, _, _| {})
+    }
+
+    /// Like `run`, calling `finished` with the indices of both entrants and
+    /// the report of every pairing as it ends.
+    pub fn run_with(&self, mut finished: impl FnMut(usize, usize, &Report)) -> Standings {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut pairings = Vec::new();
+        for first in 0..self.entrants.len() {
+            for second in first + 1..self.entrants.len() {
+                let mut simulation = Simulation::new(
+                    self.entrants[first].factory.clone(),
+                    self.entrants[second].factory.clone(),
+                )
+                .games(self.games)
+                .seed(rng.gen())
+                .game_mode(self.game_mode)
+                .duplicate(self.duplicate);
+                if let Some(threads) = self.threads {
+                    simulation = simulation.threads(threads);
+                }
+                let report = simulation.run();
+                finished(first, second, &report);
+                pairings.push(Pairing { first, second, report });
+            }
+        }
+        Standings::new(&self.entrants, pairings)
+    }
+}
+
+/// The games of two entrants, by their index in the tournament.
+#[derive(Debug, Clone, Copy)]
+pub struct Pairing {
+    pub first: usize,
+    pub second: usize,
+    /// as seen by `first`
+    pub report: Report,
+}
+
+/// One line of the leaderboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    pub rating: f64,
+    pub games: u64,
+    pub wins: u64,
+    pub draws: u64,
+    pub losses: u64,
+    /// points won less points lost, over every game
+    pub margin: i64,
+}
+
+impl Standing {
+    /// Wins and half the draws, as a share of the results.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / (self.wins + self.draws + self.losses).max(1) as f64
+    }
+}
+
+/// The result of a tournament, best rated first.
+pub struct Standings {
+    pub standings: Vec<Standing>,
+    pub pairings: Vec<Pairing>,
+}
+
+impl Standings {
+    fn new(entrants: &[Entrant], pairings: Vec<Pairing>) -> Standings {
+        let mut standings: Vec<Standing> = entrants
+            .iter()
+            .map(|entrant| Standing {
+                name: entrant.name.clone(),
+                rating: MEAN_RATING,
+                games: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+                margin: 0,
+            })
+            .collect();
+        for pairing in pairings.iter() {
+            let report = &pairing.report;
+            let first = &mut standings[pairing.first];
+            first.games += report.games;
+            first.wins += report.wins;
+            first.draws += report.draws;
+            first.losses += report.losses;
+            first.margin += report.margin;
+            let second = &mut standings[pairing.second];
+            second.games += report.games;
+            second.wins += report.losses;
+            second.draws += report.draws;
+            second.losses += report.wins;
+            second.margin -= report.margin;
+        }
+
+        for (standing, rating) in standings.iter_mut().zip(ratings(entrants.len(), &pairings)) {
+            standing.rating = rating;
+        }
+        standings.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        Standings { standings, pairings }
+    }
+}
+
+impl fmt::Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.standings.iter().map(|standing| standing.name.len()).max().unwrap_or(0).max(6);
+        writeln!(
+            f,
+            "{:>4}  {:<width$}  {:>6}  {:>6}  {:>8} {:>8} {:>8}  {:>7}",
+            "rank", "player", "elo", "score", "won", "drawn", "lost", "margin"
+        )?;
+        for (rank, standing) in self.standings.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4}  {:<width$}  {:>6.0}  {:>5.1}%  {:>8} {:>8} {:>8}  {:>+7.2}",
+                rank + 1,
+                standing.name,
+                standing.rating,
+                100.0 * standing.score(),
+                standing.wins,
+                standing.draws,
+                standing.losses,
+                standing.margin as f64 / standing.games.max(1) as f64,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Elo ratings fitting every result at once, a Bradley-Terry model solved
+/// by minorization-maximization with a draw counting half a win. Every
+/// pair gets one made-up draw as well, so an entrant that never scored
+/// still has a finite rating. The ratings average `MEAN_RATING`.
+fn ratings(count: usize, pairings: &[Pairing]) -> Vec<f64> {
+    let mut scores = vec![vec![0.5; count]; count];
+    let mut games = vec![vec![1.0; count]; count];
+    for pairing in pairings {
+        let report = &pairing.report;
+        let drawn = report.draws as f64 / 2.0;
+        scores[pairing.first][pairing.second] += report.wins as f64 + drawn;
+        scores[pairing.second][pairing.first] += report.losses as f64 + drawn;
+        games[pairing.first][pairing.second] += report.results() as f64;
+        games[pairing.second][pairing.first] += report.results() as f64;
+    }
+
+    let mut strengths = vec![1.0; count];
+    for _ in 0..FIT_ROUNDS {
+        for player in 0..count {
+            let others = (0..count).filter(|other| *other != player);
+            let score: f64 = others.clone().map(|other| scores[player][other]).sum();
+            let expected: f64 = others
+                .map(|other| games[player][other] / (strengths[player] + strengths[other]))
+                .sum();
+            strengths[player] = score / expected;
+        }
+        let mean = strengths.iter().map(|strength: &f64| strength.ln()).sum::<f64>() / count as f64;
+        for strength in strengths.iter_mut() {
+            *strength /= mean.exp();
+        }
+    }
+    strengths
+        .iter()
+        .map(|strength| MEAN_RATING + 400.0 * strength.log10())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find, ratings, Pairing, Tournament};
+    use crate::simulation::Report;
+
+    fn pairing(first: usize, second: usize, wins: u64, draws: u64, losses: u64) -> Pairing {
+        Pairing {
+            first,
+            second,
+            report: Report {
+                games: wins + draws + losses,
+                wins,
+                draws,
+                losses,
+                ..Report::default()
+            },
+        }
+    }
+
+    #[test]
+    fn winning_three_in_four_is_worth_about_190_points() {
+        let ratings = ratings(2, &[pairing(0, 1, 7500, 0, 2500)]);
+        assert!((ratings[0] - ratings[1] - 190.8).abs() < 1.0, "{:?}", ratings);
+        assert!((ratings[0] + ratings[1] - 3000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ratings_follow_the_order_of_strength() {
+        let ratings = ratings(
+            3,
+            &[pairing(0, 1, 60, 10, 30), pairing(1, 2, 60, 10, 30), pairing(0, 2, 100, 0, 0)],
+        );
+        assert!(ratings[0] > ratings[1] && ratings[1] > ratings[2], "{:?}", ratings);
+        assert!(ratings[2].is_finite());
+    }
+
+    #[test]
+    fn ranks_the_greedy_player_above_the_first_possible_tile() {
+        let entrants = ["first-possible", "greedy", "observent"].map(|name| find(name).unwrap()).to_vec();
+        let standings = Tournament::new(entrants).games(300).threads(2).seed(1).run();
+
+        assert_eq!(standings.pairings.len(), 3);
+        let rank = |name: &str| standings.standings.iter().position(|standing| standing.name == name).unwrap();
+        assert!(rank("greedy") < rank("first-possible"), "{}", standings);
+        for standing in standings.standings.iter() {
+            assert_eq!(standing.games, 600);
+            assert_eq!(standing.wins + standing.draws + standing.losses, 600);
+        }
+    }
+}
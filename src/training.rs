@@ -0,0 +1,220 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::{
+    game::{Game, GameMode, GameState, Winner},
+    network::{Network, Optimizer, Sample},
+    neural_ai_player::{NeuralAIPlayer, Trajectory},
+    player::Player,
+};
+
+/// A copy of the weights joins the opponents this often, in iterations.
+const SNAPSHOT_EVERY: u32 = 10;
+/// The oldest snapshot makes room beyond this many.
+const MAX_SNAPSHOTS: usize = 16;
+const BATCH: usize = 256;
+
+/// How one `Training::iterate` went.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Report {
+    pub iteration: u32,
+    /// averaged over the batches
+    pub loss: f32,
+    pub samples: usize,
+    /// the games against past snapshots, the rest being self-play
+    pub wins: u32,
+    pub losses: u32,
+}
+
+/// Self-play reinforcement learning: the network plays itself and past
+/// snapshots of itself, sampling its moves, and learns from every move
+/// taken by policy gradient.
+pub struct Training {
+    network: Network,
+    optimizer: Optimizer,
+    /// parameters of earlier iterations
+    snapshots: Vec<Vec<f32>>,
+    rng: StdRng,
+    games: u32,
+    game_mode: GameMode,
+    iteration: u32,
+}
+
+impl Training {
+    pub fn new(network: Network) -> Self {
+        Training {
+            optimizer: Optimizer::new(&network, 1e-3),
+            network,
+            snapshots: Vec::new(),
+            rng: StdRng::from_entropy(),
+            games: 64,
+            game_mode: GameMode::Block,
+            iteration: 0,
+        }
+    }
+
+    /// How many games to play per iteration.
+    pub fn games(mut self, games: u32) -> Self {
+        assert!(games > 0, "at least one game is needed");
+        self.games = games;
+        self
+    }
+
+    pub fn learning_rate(mut self, learning_rate: f64) -> Self {
+        self.optimizer = Optimizer::new(&self.network, learning_rate);
+        self
+    }
+
+    pub fn game_mode(mut self, game_mode: GameMode) -> Self {
+        self.game_mode = game_mode;
+        self
+    }
+
+    /// Makes the games repeatable.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    /// Plays a round of games and learns from them. Once there are
+    /// snapshots, half the games are against one of them.
+    pub fn iterate(&mut self) -> Report {
+        self.iteration += 1;
+        let mut samples = Vec::new();
+        let (mut wins, mut losses) = (0, 0);
+        for game in 0..self.games {
+            // take turns moving first
+            let learner = (game % 2) as usize;
+            let snapshot = if game % 4 < 2 {
+                None
+            } else {
+                self.snapshots.choose(&mut self.rng).cloned()
+            };
+            let against_snapshot = snapshot.is_some();
+
+            let (winner, trajectories) = self.play(learner, snapshot);
+            for (seat, trajectory) in trajectories {
+                let reward = match winner {
+                    Some(winner) if winner.contains(seat) => 1.0,
+                    Some(_) => -1.0,
+                    None => 0.0,
+                };
+                samples.extend(trajectory.take().into_iter().map(|sample| Sample { reward, ..sample }));
+            }
+            match winner {
+                Some(winner) if against_snapshot && winner.contains(learner) => wins += 1,
+                Some(_) if against_snapshot => losses += 1,
+                _ => {}
+            }
+        }
+
+        samples.shuffle(&mut self.rng);
+        let batches: Vec<f32> = samples
+            .chunks(BATCH)
+            .map(|batch| self.network.learn(&mut self.optimizer, batch))
+            .collect();
+
+        if self.iteration.is_multiple_of(SNAPSHOT_EVERY) {
+            if self.snapshots.len() == MAX_SNAPSHOTS {
+                self.snapshots.remove(0);
+            }
+            self.snapshots.push(self.network.parameters());
+        }
+
+        Report {
+            iteration: self.iteration,
+            loss: batches.iter().sum::<f32>() / batches.len().max(1) as f32,
+            samples: samples.len(),
+            wins,
+            losses,
+        }
+    }
+
+    /// One game of the network in seat `learner` against `snapshot`, or
+    /// against itself without one. Returns the winner and the moves of
+    /// every seat the network played.
+    fn play(&mut self, learner: usize, snapshot: Option<Vec<f32>>) -> (Option<Winner>, Vec<(usize, Trajectory)>) {
+        let mut trajectories = Vec::new();
+        let mut players: Vec<Box<dyn Player>> = Vec::new();
+        for seat in 0..2 {
+            let mut network = self.network.clone();
+            let player = match &snapshot {
+                Some(parameters) if seat != learner => {
+                    network.set_parameters(parameters).unwrap();
+                    NeuralAIPlayer::new(network).exploring(self.rng.gen())
+                }
+                _ => {
+                    let trajectory = Trajectory::default();
+                    trajectories.push((seat, trajectory.clone()));
+                    NeuralAIPlayer::new(network).exploring(self.rng.gen()).recording(trajectory)
+                }
+            };
+            players.push(Box::new(player));
+        }
+
+        let mut game = Game::from_seed(self.rng.gen(), players).game_mode(self.game_mode);
+        loop {
+            let (next, state) = game.play();
+            game = next;
+            if let GameState::Finished { winner, .. } = state {
+                return (winner, trajectories);
+            }
+        }
+    }
+
+    /// Writes the weights to `iteration-<n>.weights` and `latest.weights` in
+    /// `directory`, creating it if needed. Returns the first path.
+    pub fn checkpoint<P: AsRef<Path>>(&self, directory: P) -> io::Result<PathBuf> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+        let path = directory.join(format!("iteration-{}.weights", self.iteration));
+        self.network.save(&path)?;
+        self.network.save(directory.join("latest.weights"))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Training, SNAPSHOT_EVERY};
+    use crate::network::Network;
+
+    #[test]
+    fn plays_snapshots_once_it_has_some() {
+        let mut training = Training::new(Network::new(1)).games(4).seed(1);
+        for _ in 0..SNAPSHOT_EVERY {
+            let report = training.iterate();
+            assert!(report.loss.is_finite());
+            assert!(report.samples > 0);
+            assert_eq!(report.wins + report.losses, 0);
+        }
+
+        let report = training.iterate();
+        assert_eq!(report.iteration, SNAPSHOT_EVERY + 1);
+        // a blocked game may have no winner
+        assert!(report.wins + report.losses <= 2);
+        assert!(report.wins + report.losses > 0);
+    }
+
+    #[test]
+    fn checkpoints_load_back() {
+        let mut training = Training::new(Network::new(2)).games(2).seed(2);
+        training.iterate();
+        let directory = std::env::temp_dir().join(format!("rust_domino_checkpoints_{}", std::process::id()));
+
+        let path = training.checkpoint(&directory).unwrap();
+        let loaded = Network::load(directory.join("latest.weights")).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(path.ends_with("iteration-1.weights"));
+        assert_eq!(loaded.parameters(), training.network().parameters());
+    }
+}
@@ -0,0 +1,566 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event as TerminalEvent, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::{
+    choose_tile_strategy::{Choice, ChooseTileStrategy},
+    game::{GameMode, GameState, Winner},
+    game_move::{legal_moves, Move, Side},
+    game_observer::GameObserver,
+    hand::Hand,
+    knowledge::Knowledge,
+    player::Player,
+    snake::Snake,
+    tile::Tile,
+};
+
+/// How many of the latest events the screen lists.
+const LOG_LINES: usize = 6;
+
+const HELP: &str = "←/→ pick a tile   ↑/↓ pick the end   enter play   u take back   q quit";
+
+/// How a piece of text stands out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Look {
+    Plain,
+    Title,
+    /// a tile that fits the snake
+    Legal,
+    /// a tile that doesn't
+    Illegal,
+    Selected,
+}
+
+type Line = Vec<(String, Look)>;
+
+fn line(text: impl Into<String>, look: Look) -> Line {
+    vec![(text.into(), look)]
+}
+
+/// Something that happened since, as the seat saw it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    /// `None` for the hidden tiles of other seats
+    Drew { seat: usize, tiles: Vec<Option<Tile>> },
+    Played { seat: usize, tile: Tile },
+    Passed { seat: usize, pips: [u8; 2] },
+    IPlayed(Move),
+}
+
+/// What a key asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Input {
+    Play(Move),
+    TakeBack,
+    Quit,
+}
+
+/// Switches the terminal to a raw alternate screen for as long as it lives.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// A human at the terminal, on a full screen showing only what their seat
+/// can see: their own hand, the snake, how many tiles are left in the
+/// boneyard and how many every other seat holds. The arrow keys pick a tile
+/// and the end it goes on, with the tiles that fit highlighted. Quitting,
+/// or the terminal failing, leaves the screen and answers `Choice::Quit`.
+pub struct TuiPlayer {
+    knowledge: Knowledge,
+    log: VecDeque<Entry>,
+    /// the tiles dealt to me, to tell how many I played since
+    dealt: usize,
+    drawn: usize,
+    /// an index into the hand
+    selected: usize,
+    side: Side,
+    /// why the last key did nothing
+    hint: Option<String>,
+    screen: Option<Screen>,
+}
+
+impl Default for TuiPlayer {
+    fn default() -> Self {
+        TuiPlayer {
+            knowledge: Knowledge::default(),
+            log: VecDeque::new(),
+            dealt: 0,
+            drawn: 0,
+            selected: 0,
+            side: Side::Right,
+            hint: None,
+            screen: None,
+        }
+    }
+}
+
+impl TuiPlayer {
+    fn push(&mut self, entry: Entry) {
+        // a run of draws by the same seat is one line
+        if let (Some(Entry::Drew { seat, tiles }), Entry::Drew { seat: drawer, tiles: drawn }) = (self.log.back_mut(), &entry) {
+            if *seat == *drawer {
+                tiles.extend(drawn);
+                return;
+            }
+        }
+        if self.log.len() == LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(entry);
+    }
+
+    fn name(&self, seat: usize) -> String {
+        if seat == self.knowledge.seat {
+            "You".to_string()
+        } else if Some(seat) == self.knowledge.partner {
+            format!("Your partner in seat {}", seat)
+        } else {
+            format!("Seat {}", seat)
+        }
+    }
+
+    fn describe(&self, entry: &Entry) -> String {
+        match entry {
+            Entry::Drew { seat, tiles } if *seat == self.knowledge.seat => {
+                let tiles: Vec<String> = tiles.iter().flatten().map(|tile| format!("{:?}", tile)).collect();
+                format!("You drew {}", tiles.join(" "))
+            }
+            Entry::Drew { seat, tiles } if tiles.len() == 1 => format!("{} drew a tile", self.name(*seat)),
+            Entry::Drew { seat, tiles } => format!("{} drew {} tiles", self.name(*seat), tiles.len()),
+            Entry::Played { seat, tile } => format!("{} played {:?}", self.name(*seat), tile),
+            Entry::Passed { seat, pips } => format!("{} couldn't play on {} or {}", self.name(*seat), pips[0], pips[1]),
+            Entry::IPlayed(mv) => format!("You played {:?} on the {} end", mv.tile, side_name(mv.side)),
+        }
+    }
+
+    /// The move the selection stands for, `None` when the tile doesn't fit.
+    fn target(&self, hand: &Hand, snake: &Snake) -> Option<Move> {
+        let tile = hand.tiles.get(self.selected)?;
+        let moves: Vec<Move> = legal_moves(hand, snake).into_iter().filter(|mv| mv.tile == *tile).collect();
+        moves.iter().find(|mv| mv.side == self.side).or(moves.first()).copied()
+    }
+
+    fn handle(&mut self, key: KeyEvent, hand: &Hand, snake: &Snake) -> Option<Input> {
+        self.hint = None;
+        let count = hand.tiles.len();
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Some(Input::Quit),
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Input::Quit),
+            KeyCode::Left | KeyCode::Char('h') => self.selected = (self.selected + count - 1) % count,
+            KeyCode::Right | KeyCode::Char('l') => self.selected = (self.selected + 1) % count,
+            KeyCode::Up | KeyCode::Down | KeyCode::Tab | KeyCode::Char('k') | KeyCode::Char('j') => {
+                self.side = match self.side {
+                    Side::Left => Side::Right,
+                    Side::Right => Side::Left,
+                }
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => match self.target(hand, snake) {
+                Some(mv) => return Some(Input::Play(mv)),
+                None => self.hint = Some(format!("{:?} doesn't fit either end", hand.tiles[self.selected])),
+            },
+            KeyCode::Char('u') => {
+                if self.dealt + self.drawn > count {
+                    return Some(Input::TakeBack);
+                }
+                self.hint = Some("You haven't played a tile yet".to_string());
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Every seat but mine with the tiles it holds.
+    fn others(&self) -> Line {
+        let mut others = Vec::new();
+        for (seat, possible) in self.knowledge.hands.iter().enumerate() {
+            if seat != self.knowledge.seat {
+                let tiles = if possible.size == 1 { "tile" } else { "tiles" };
+                others.push((format!("{}: {} {}   ", self.name(seat), possible.size, tiles), Look::Plain));
+            }
+        }
+        others
+    }
+
+    fn snake_lines(snake: &Snake, width: usize) -> Vec<Line> {
+        let mut lines = match (snake.left(), snake.right()) {
+            (Some(left), Some(right)) => vec![line(format!("Snake, ends {} and {}:", left, right), Look::Title)],
+            _ => vec![line("The snake is empty, any tile starts it", Look::Title)],
+        };
+        // a glyph and a space, glyphs taking up to two columns
+        for tiles in snake.tiles.chunks((width.saturating_sub(2) / 3).max(1)) {
+            let glyphs: String = tiles.iter().map(|tile| format!(" {}", tile.horizontal_glyph())).collect();
+            lines.push(line(glyphs, Look::Plain));
+        }
+        lines
+    }
+
+    /// The screen on my turn.
+    fn lines(&self, hand: &Hand, snake: &Snake, width: usize) -> Vec<Line> {
+        let mode = match self.knowledge.game_mode {
+            GameMode::Block => "block",
+            GameMode::Draw => "draw",
+        };
+        let others: usize = self
+            .knowledge
+            .hands
+            .iter()
+            .enumerate()
+            .filter(|(seat, _)| *seat != self.knowledge.seat)
+            .map(|(_, possible)| possible.size as usize)
+            .sum();
+        let boneyard = Knowledge::unseen(hand, snake).len().saturating_sub(others);
+
+        let mut lines = vec![line(format!("Dominoes, seat {}, {} game", self.knowledge.seat, mode), Look::Title)];
+        let mut counts = self.others();
+        counts.push((format!("Boneyard: {}", boneyard), Look::Plain));
+        lines.push(counts);
+        lines.push(Line::new());
+        lines.extend(Self::snake_lines(snake, width));
+        lines.push(Line::new());
+
+        lines.push(line("Your hand:", Look::Title));
+        let playable: Vec<Tile> = legal_moves(hand, snake).iter().map(|mv| mv.tile).collect();
+        let mut tiles = Line::new();
+        for (index, tile) in hand.tiles.iter().enumerate() {
+            let look = if index == self.selected {
+                Look::Selected
+            } else if playable.contains(tile) {
+                Look::Legal
+            } else {
+                Look::Illegal
+            };
+            tiles.push((" ".to_string(), Look::Plain));
+            tiles.push((format!("{} {}|{}", tile.vertical_glyph(), tile.left, tile.right), look));
+        }
+        lines.push(tiles);
+        lines.push(match (&self.hint, self.target(hand, snake)) {
+            (Some(hint), _) => line(hint.clone(), Look::Illegal),
+            (None, Some(mv)) => line(format!("Play {:?} on the {} end", mv.tile, side_name(mv.side)), Look::Legal),
+            (None, None) => line(format!("{:?} doesn't fit", hand.tiles[self.selected]), Look::Illegal),
+        });
+        lines.push(Line::new());
+
+        lines.extend(self.log.iter().map(|entry| line(self.describe(entry), Look::Plain)));
+        lines.push(Line::new());
+        lines.push(line(HELP, Look::Plain));
+        lines
+    }
+
+    /// The screen once the game is over, every hand turned up.
+    fn final_lines(&self, state: &GameState, hands: &[Hand], snake: &Snake, width: usize) -> Vec<Line> {
+        let seat = self.knowledge.seat;
+        let result = match state {
+            GameState::Finished { winner: Some(winner), scores } => {
+                let (won, points) = match *winner {
+                    Winner::Seat(winner) => (winner == seat, scores[winner]),
+                    Winner::Team(team) => (team == seat % 2, scores[team]),
+                };
+                let who = match *winner {
+                    Winner::Seat(winner) => self.name(winner),
+                    Winner::Team(_) if won => "Your team".to_string(),
+                    Winner::Team(team) => format!("Team {}", team),
+                };
+                let look = if won { Look::Legal } else { Look::Illegal };
+                line(format!("{} won {} points", who, points), look)
+            }
+            _ => line("Blocked with even hands, nobody won", Look::Title),
+        };
+
+        let mut lines = vec![result, Line::new()];
+        lines.extend(Self::snake_lines(snake, width));
+        lines.push(Line::new());
+        for (other, hand) in hands.iter().enumerate() {
+            let glyphs: String = hand.tiles.iter().map(|tile| format!(" {}", tile.vertical_glyph())).collect();
+            let pips: u32 = hand.tiles.iter().map(|tile| tile.score() as u32).sum();
+            lines.push(line(format!("{} held {} pips:{}", self.name(other), pips, glyphs), Look::Plain));
+        }
+        lines.push(Line::new());
+        lines.extend(self.log.iter().map(|entry| line(self.describe(entry), Look::Plain)));
+        lines.push(Line::new());
+        lines.push(line("Press any key", Look::Plain));
+        lines
+    }
+
+    fn draw(lines: &[Line]) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        queue!(stdout, Clear(ClearType::All))?;
+        for (row, line) in lines.iter().enumerate() {
+            queue!(stdout, MoveTo(0, row as u16))?;
+            for (text, look) in line {
+                match look {
+                    Look::Plain => {}
+                    Look::Title => queue!(stdout, SetAttribute(Attribute::Bold))?,
+                    Look::Legal => queue!(stdout, SetForegroundColor(Color::Green))?,
+                    Look::Illegal => queue!(stdout, SetForegroundColor(Color::DarkGrey))?,
+                    Look::Selected => queue!(stdout, SetAttribute(Attribute::Reverse))?,
+                }
+                queue!(stdout, Print(text), SetAttribute(Attribute::Reset), ResetColor)?;
+            }
+        }
+        stdout.flush()
+    }
+
+    fn width() -> usize {
+        terminal::size().map_or(80, |(columns, _)| columns as usize)
+    }
+
+    /// The next key pressed, resizes and releases are skipped.
+    fn key() -> io::Result<KeyEvent> {
+        loop {
+            if let TerminalEvent::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    return Ok(key);
+                }
+            }
+        }
+    }
+
+    fn show(&mut self) -> io::Result<()> {
+        if self.screen.is_none() {
+            self.screen = Some(Screen::enter()?);
+        }
+        Ok(())
+    }
+
+    /// Shows my turn until a key asks for something.
+    fn ask(&mut self, hand: &Hand, snake: &Snake) -> io::Result<Input> {
+        self.show()?;
+        loop {
+            Self::draw(&self.lines(hand, snake, Self::width()))?;
+            if let Some(input) = self.handle(Self::key()?, hand, snake) {
+                return Ok(input);
+            }
+        }
+    }
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Left => "left",
+        Side::Right => "right",
+    }
+}
+
+impl Player for TuiPlayer {}
+
+impl ChooseTileStrategy for TuiPlayer {
+    /// A human may always quit, which no move stands for. `Game` asks
+    /// through `choose`, so this is never called.
+    fn choose_move(&mut self, _hand: &Hand, _snake: &Snake) -> Move {
+        unreachable!("a TuiPlayer is only asked through choose")
+    }
+
+    fn choose(&mut self, hand: &Hand, snake: &Snake) -> Choice {
+        let Some(fits) = legal_moves(hand, snake).first().copied() else {
+            // the game only asks while a tile fits, there is nothing to pick
+            self.screen = None;
+            return Choice::Quit;
+        };
+        if self.target(hand, snake).is_none() {
+            // start on a tile that fits
+            if let Some(index) = hand.tiles.iter().position(|tile| *tile == fits.tile) {
+                self.selected = index;
+            }
+        }
+
+        match self.ask(hand, snake) {
+            Ok(Input::Play(mv)) => {
+                self.push(Entry::IPlayed(mv));
+                Choice::Play(mv)
+            }
+            Ok(Input::TakeBack) => Choice::TakeBack,
+            Ok(Input::Quit) => {
+                self.screen = None;
+                Choice::Quit
+            }
+            Err(error) => {
+                self.screen = None;
+                eprintln!("can't use the terminal: {}", error);
+                Choice::Quit
+            }
+        }
+    }
+}
+
+impl GameObserver for TuiPlayer {
+    fn game_started(
+        &mut self,
+        seat: usize,
+        hand: &Hand,
+        hand_sizes: &[usize],
+        snake: &Snake,
+        game_mode: GameMode,
+        partner: Option<usize>,
+    ) {
+        self.knowledge.game_started(seat, hand, hand_sizes, snake, game_mode, partner);
+        // told again from the start after a take back
+        self.log.clear();
+        self.dealt = hand.tiles.len();
+        self.drawn = 0;
+        self.hint = None;
+    }
+
+    fn opponent_drew_tile(&mut self, seat: usize) {
+        self.knowledge.opponent_drew_tile(seat);
+        self.push(Entry::Drew { seat, tiles: vec![None] });
+    }
+
+    fn i_drew_tile(&mut self, tile: Tile) {
+        self.knowledge.i_drew_tile(tile);
+        self.drawn += 1;
+        self.push(Entry::Drew {
+            seat: self.knowledge.seat,
+            tiles: vec![Some(tile)],
+        });
+    }
+
+    fn opponent_played_tile(&mut self, seat: usize, tile: Tile) {
+        self.knowledge.opponent_played_tile(seat, tile);
+        self.push(Entry::Played { seat, tile });
+    }
+
+    fn opponent_was_blocked(&mut self, seat: usize, pips: [u8; 2]) {
+        self.knowledge.opponent_was_blocked(seat, pips);
+        self.push(Entry::Passed { seat, pips });
+    }
+
+    fn game_finished(&mut self, state: &GameState, hands: &[Hand], snake: &Snake) {
+        // the game is over either way, so a failing terminal only skips the
+        // final screen
+        let _ = self
+            .show()
+            .and_then(|()| Self::draw(&self.final_lines(state, hands, snake, Self::width())))
+            .and_then(|()| Self::key());
+        self.screen = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{Input, Look, TuiPlayer};
+    use crate::{
+        choose_tile_strategy::{Choice, ChooseTileStrategy},
+        game::GameMode,
+        game_move::{Move, Side},
+        game_observer::GameObserver,
+        hand::Hand,
+        position::fixtures,
+        snake::Snake,
+        tile::Tile,
+    };
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    /// Seat 0 of two holding [3|4] [6|6] [5|1] on a snake of [3|5].
+    fn setup() -> (TuiPlayer, Hand, Snake) {
+        let hand = fixtures::hand(&[(3, 4), (6, 6), (5, 1)]);
+        let mut snake = Snake::new();
+        snake.play(Tile::new(3, 5), Side::Right).unwrap();
+        let mut player = TuiPlayer::default();
+        player.game_started(0, &hand, &[3, 7], &snake, GameMode::Draw, None);
+        (player, hand, snake)
+    }
+
+    #[test]
+    fn the_arrows_pick_a_tile_and_an_end() {
+        let (mut player, hand, snake) = setup();
+        assert_eq!(
+            player.handle(key(KeyCode::Enter), &hand, &snake),
+            Some(Input::Play(Move::new(Tile::new(3, 4), Side::Left)))
+        );
+
+        // [6|6] doesn't fit, so nothing is played
+        player.handle(key(KeyCode::Right), &hand, &snake);
+        assert_eq!(player.handle(key(KeyCode::Enter), &hand, &snake), None);
+        assert!(player.hint.is_some());
+
+        player.handle(key(KeyCode::Right), &hand, &snake);
+        assert_eq!(
+            player.handle(key(KeyCode::Enter), &hand, &snake),
+            Some(Input::Play(Move::new(Tile::new(5, 1), Side::Right)))
+        );
+        // wrapping around to the first tile again
+        player.handle(key(KeyCode::Right), &hand, &snake);
+        assert_eq!(player.selected, 0);
+        player.handle(key(KeyCode::Left), &hand, &snake);
+        assert_eq!(player.selected, 2);
+    }
+
+    #[test]
+    fn up_and_down_switch_the_end_when_both_fit() {
+        let hand = Hand {
+            tiles: [Tile::new(3, 5)].into_iter().collect(),
+        };
+        let mut snake = Snake::new();
+        snake.play(Tile::new(5, 3), Side::Right).unwrap();
+        let mut player = TuiPlayer::default();
+        player.game_started(0, &hand, &[1, 7], &snake, GameMode::Block, None);
+
+        assert_eq!(player.target(&hand, &snake), Some(Move::new(Tile::new(3, 5), Side::Right)));
+        player.handle(key(KeyCode::Up), &hand, &snake);
+        assert_eq!(player.target(&hand, &snake), Some(Move::new(Tile::new(3, 5), Side::Left)));
+    }
+
+    #[test]
+    fn quits_without_a_screen_when_nothing_fits() {
+        let (mut player, _, snake) = setup();
+        let hand = fixtures::hand(&[(6, 6)]);
+        assert!(matches!(player.choose(&hand, &snake), Choice::Quit));
+        assert!(player.screen.is_none());
+        assert!(matches!(player.choose(&Hand::default(), &snake), Choice::Quit));
+    }
+
+    #[test]
+    fn nothing_to_take_back_before_the_first_tile() {
+        let (mut player, mut hand, snake) = setup();
+        assert_eq!(player.handle(key(KeyCode::Char('u')), &hand, &snake), None);
+
+        hand.tiles.remove(0);
+        assert_eq!(player.handle(key(KeyCode::Char('u')), &hand, &snake), Some(Input::TakeBack));
+    }
+
+    #[test]
+    fn shows_only_what_the_seat_can_see() {
+        let (mut player, hand, snake) = setup();
+        player.opponent_drew_tile(1);
+        player.opponent_drew_tile(1);
+        player.opponent_played_tile(1, Tile::new(0, 0));
+
+        let lines = player.lines(&hand, &snake, 80);
+        let text: Vec<String> = lines.iter().map(|line| line.iter().map(|(text, _)| text.as_str()).collect()).collect();
+        // 28 tiles less the snake, my 3 and seat 1's 7 + 2 - 1
+        assert!(text.contains(&"Seat 1: 8 tiles   Boneyard: 16".to_string()), "{:#?}", text);
+        assert!(text.contains(&"Seat 1 drew 2 tiles".to_string()));
+        assert!(text.contains(&"Seat 1 played [0|0]".to_string()));
+
+        let hand_line = lines.iter().find(|line| line.iter().any(|(text, _)| text.ends_with("6|6"))).unwrap();
+        let looks: Vec<Look> = hand_line.iter().filter(|(text, _)| text != " ").map(|(_, look)| *look).collect();
+        assert_eq!(looks, vec![Look::Selected, Look::Illegal, Look::Legal]);
+    }
+}